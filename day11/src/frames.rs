@@ -0,0 +1,76 @@
+use crate::{Cell, Seating};
+use std::io::{self, Write};
+
+/// Writes every generation as a text map, each one headed by its number
+/// and separated by a blank line.
+pub fn write_text(generations: &[Seating], out: &mut impl Write) -> io::Result<()> {
+    for (generation, seating) in generations.iter().enumerate() {
+        if generation > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "== generation {} ==", generation)?;
+        writeln!(out, "{}", seating)?;
+    }
+
+    Ok(())
+}
+
+fn colour(cell: Option<Cell>) -> [u8; 3] {
+    match cell {
+        None => [40, 40, 40],
+        Some(Cell::Empty) => [60, 170, 80],
+        Some(Cell::Occupied) => [210, 50, 50],
+    }
+}
+
+/// Writes a single generation as a binary PPM image, with every tile drawn
+/// as a `scale` by `scale` square.
+pub fn write_ppm(seating: &Seating, scale: usize, out: &mut impl Write) -> io::Result<()> {
+    let grid = seating.grid();
+    let height = grid.len();
+    let width = grid.first().map_or(0, |row| row.len());
+
+    write!(out, "P6\n{} {}\n255\n", width * scale, height * scale)?;
+    for row in &grid {
+        let pixels: Vec<u8> = row
+            .iter()
+            .flat_map(|&cell| std::iter::repeat(colour(cell)).take(scale))
+            .flat_map(|rgb| rgb.to_vec())
+            .collect();
+        for _ in 0..scale {
+            out.write_all(&pixels)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, Rules};
+
+    #[test]
+    fn text_frames() {
+        let seating = Seating::new(&parse_input("L.L\n.LL").unwrap(), Rules::part_a());
+        let generations: Vec<Seating> = seating.into_iter().collect();
+        let mut out = vec![];
+        write_text(&generations, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "== generation 0 ==\nL.L\n.LL\n\n== generation 1 ==\n#.#\n.##\n"
+        );
+    }
+
+    #[test]
+    fn ppm() {
+        let seating = Seating::new(&parse_input(".L").unwrap(), Rules::part_a());
+        let mut out = vec![];
+        write_ppm(&seating, 2, &mut out).unwrap();
+
+        let header = b"P6\n4 2\n255\n";
+        let row = [[40, 40, 40], [40, 40, 40], [60, 170, 80], [60, 170, 80]].concat();
+        assert_eq!(out, [&header[..], &row, &row].concat());
+    }
+}
@@ -1,76 +1,291 @@
+mod frames;
+
 #[allow(unused_imports)]
 use shared::prelude::*;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::rc::Rc;
 
-const INPUT: &'static str = include_str!("./input.txt");
+const INPUT: &str = include_str!("./input.txt");
 
-type Data = HashMap<Point, Cell>;
+type Data = Map;
 type Solution = usize;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Cell {
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Cell {
     Empty,
     Occupied,
 }
 
+/// The parsed floor plan: its full size and the starting state of every
+/// seat. Everything else is floor.
 #[derive(Debug, Clone, PartialEq)]
-struct Seating {
-    map: HashMap<Point, Cell>,
+struct Map {
     width: usize,
     height: usize,
-    neighbour_threshold: usize,
-    look_further: bool,
+    seats: HashMap<Point, Cell>,
 }
 
-impl Seating {
-    fn new(map: &HashMap<Point, Cell>, neighbour_threshold: usize, look_further: bool) -> Self {
-        let width = map.keys().map(|Point(_, col)| *col).max().unwrap_or(0) + 1;
-        let height = map.keys().map(|Point(row, _)| *row).max().unwrap_or(0) + 1;
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    Char(char, Point),
+    /// A row whose length differs from the first row's.
+    Length {
+        row: usize,
+        len: usize,
+        width: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Char(c, Point(row, col)) => write!(
+                f,
+                "unexpected '{}' at line {}, column {}",
+                c,
+                row + 1,
+                col + 1
+            ),
+            ParseError::Length { row, len, width } => {
+                write!(f, "line {} has {} tiles, expected {}", row + 1, len, width)
+            }
+        }
+    }
+}
+
+/// When seats change. An empty seat is taken when at most `birth` of the
+/// seats it considers are occupied, an occupied seat is left when at least
+/// `survival` of them are. Seats only consider the first seat they see in
+/// each direction, up to `view_distance` tiles away if set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rules {
+    birth: usize,
+    survival: usize,
+    view_distance: Option<usize>,
+}
+
+impl Rules {
+    fn part_a() -> Self {
+        Rules {
+            birth: 0,
+            survival: 4,
+            view_distance: Some(1),
+        }
+    }
+
+    fn part_b() -> Self {
+        Rules {
+            birth: 0,
+            survival: 5,
+            view_distance: None,
+        }
+    }
+}
+
+/// How a seating ended up: unchanged from `generation` on, or repeating the
+/// states from `start` on every `period` generations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Settled { generation: usize },
+    Cycle { start: usize, period: usize },
+}
+
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Where the seats are and which seats each of them takes into account,
+/// worked out once so a generation only has to follow indices. The visible
+/// seats of seat `n` are `visible[visible_start[n]..visible_start[n + 1]]`.
+#[derive(Debug)]
+struct Layout {
+    width: usize,
+    height: usize,
+    seats: Vec<Point>,
+    visible: Vec<usize>,
+    visible_start: Vec<usize>,
+}
+
+impl Layout {
+    fn new(map: &Map, view_distance: Option<usize>) -> Self {
+        let Map { width, height, .. } = *map;
+        let seats: Vec<Point> = map.seats.keys().copied().sorted().collect();
+
+        let mut index = vec![None; width * height];
+        for (idx, Point(row, col)) in seats.iter().enumerate() {
+            index[row * width + col] = Some(idx);
+        }
+        let seat_at = |Point(row, col): Point| index[row * width + col];
+
+        let mut visible = vec![];
+        let mut visible_start = vec![0];
+        for seat in &seats {
+            for &direction in &DIRECTIONS {
+                let seen = seat
+                    .iter_direction(direction, (height, width))
+                    .take(view_distance.unwrap_or(usize::MAX))
+                    .find_map(seat_at);
+
+                visible.extend(seen);
+            }
+            visible_start.push(visible.len());
+        }
 
         Self {
-            map: map.clone(),
             width,
             height,
-            neighbour_threshold,
-            look_further,
-        }
-    }
-
-    fn count_neighbours(&self, point: Point, look_further: bool) -> usize {
-        (-1..=1)
-            .cartesian_product(-1..=1)
-            .filter(|(dr, dc)| (*dr, *dc) != (0, 0))
-            .filter(|(dr, dc)| {
-                match point
-                    .iter_direction((*dr, *dc), (self.height, self.width))
-                    .skip_while(|p| look_further && self.map.get(p) == None)
-                    .next()
-                    .map(|p| self.map.get(&p))
-                {
-                    Some(Some(Cell::Occupied)) => true,
-                    _ => false,
-                }
-            })
-            .count()
+            seats,
+            visible,
+            visible_start,
+        }
+    }
+
+    fn visible(&self, seat: usize) -> &[usize] {
+        &self.visible[self.visible_start[seat]..self.visible_start[seat + 1]]
+    }
+}
+
+/// The state of every seat, in the order of `Layout::seats`. Generations
+/// are computed into a second buffer that is swapped in afterwards.
+#[derive(Debug, Clone)]
+pub struct Seating {
+    layout: Rc<Layout>,
+    cells: Vec<Cell>,
+    buffer: Vec<Cell>,
+    rules: Rules,
+}
+
+impl Seating {
+    fn new(map: &Map, rules: Rules) -> Self {
+        let layout = Layout::new(map, rules.view_distance);
+        let cells: Vec<Cell> = layout.seats.iter().map(|point| map.seats[point]).collect();
+
+        Self {
+            layout: Rc::new(layout),
+            buffer: cells.clone(),
+            cells,
+            rules,
+        }
     }
 
-    fn next_state(&self) -> Self {
-        let map = self
-            .map
+    fn count_neighbours(&self, seat: usize) -> usize {
+        self.layout
+            .visible(seat)
             .iter()
-            .map(
-                |(point, cell)| match (cell, self.count_neighbours(*point, self.look_further)) {
-                    (Cell::Occupied, n) if n >= self.neighbour_threshold => (*point, Cell::Empty),
-                    (Cell::Empty, 0) => (*point, Cell::Occupied),
-                    (cell, _) => (*point, *cell),
-                },
-            )
-            .collect::<HashMap<_, _>>();
+            .filter(|&&other| self.cells[other] == Cell::Occupied)
+            .count()
+    }
+
+    /// Moves to the next generation and returns whether any seat changed.
+    fn next_state(&mut self) -> bool {
+        let mut changed = false;
 
-        Seating::new(&map, self.neighbour_threshold, self.look_further)
+        for seat in 0..self.cells.len() {
+            let next = match (self.cells[seat], self.count_neighbours(seat)) {
+                (Cell::Occupied, n) if n >= self.rules.survival => Cell::Empty,
+                (Cell::Empty, n) if n <= self.rules.birth => Cell::Occupied,
+                (cell, _) => cell,
+            };
+
+            changed |= next != self.cells[seat];
+            self.buffer[seat] = next;
+        }
+        std::mem::swap(&mut self.cells, &mut self.buffer);
+
+        changed
+    }
+
+    /// Runs until a state comes back, and returns the last new state with
+    /// how the seating ended up. Cycles are found with Brent's algorithm, so
+    /// only a few states are kept at any time.
+    fn settle(self) -> (Seating, Outcome) {
+        let start = self.clone();
+        let mut hare = self;
+        let mut tortoise = hare.cells.clone();
+        let mut generation = 0;
+        let (mut power, mut period) = (1, 0);
+
+        loop {
+            // A state that doesn't change is the common case and doesn't
+            // need the comparison.
+            if !hare.next_state() {
+                return (hare, Outcome::Settled { generation });
+            }
+            generation += 1;
+            period += 1;
+            if hare.cells == tortoise {
+                break;
+            }
+            if period == power {
+                tortoise.clone_from(&hare.cells);
+                power *= 2;
+                period = 0;
+            }
+        }
+
+        // The cycle starts where two states `period` generations apart are
+        // the same for the first time.
+        let mut tortoise = start.clone();
+        let mut hare = start;
+        for _ in 0..period {
+            hare.next_state();
+        }
+        let mut cycle_start = 0;
+        while tortoise.cells != hare.cells {
+            tortoise.next_state();
+            hare.next_state();
+            cycle_start += 1;
+        }
+        for _ in 1..period {
+            tortoise.next_state();
+        }
+
+        (
+            tortoise,
+            Outcome::Cycle {
+                start: cycle_start,
+                period,
+            },
+        )
     }
 
     fn count_occupied(&self) -> usize {
-        self.map.values().filter(|c| **c == Cell::Occupied).count()
+        self.cells.iter().filter(|c| **c == Cell::Occupied).count()
+    }
+
+    /// Every tile by row, `None` for floor.
+    fn grid(&self) -> Vec<Vec<Option<Cell>>> {
+        let mut grid = vec![vec![None; self.layout.width]; self.layout.height];
+        for (Point(row, col), cell) in self.layout.seats.iter().zip(&self.cells) {
+            grid[*row][*col] = Some(*cell);
+        }
+
+        grid
+    }
+}
+
+impl fmt::Display for Seating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows = self.grid().into_iter().map(|row| {
+            row.into_iter()
+                .map(|cell| match cell {
+                    None => '.',
+                    Some(Cell::Empty) => 'L',
+                    Some(Cell::Occupied) => '#',
+                })
+                .collect::<String>()
+        });
+
+        write!(f, "{}", rows.collect::<Vec<_>>().join("\n"))
     }
 }
 
@@ -79,36 +294,51 @@ impl IntoIterator for Seating {
 
     type IntoIter = SeatingIterator;
 
+    /// Settles a copy first to know where to stop, so every generation is
+    /// computed twice.
     fn into_iter(self) -> Self::IntoIter {
-        let next = self.clone().next_state();
+        let (_, outcome) = self.clone().settle();
+        let len = match outcome {
+            Outcome::Settled { generation } => generation + 1,
+            Outcome::Cycle { start, period } => start + period,
+        };
 
         SeatingIterator {
             current: self,
-            next,
+            generation: 0,
+            len,
+            outcome,
         }
     }
 }
 
-struct SeatingIterator {
+/// Yields the starting state and then every generation until one would
+/// repeat an earlier state, so layouts that never settle end as well.
+/// `outcome` tells which of the two happens.
+pub struct SeatingIterator {
     current: Seating,
-    next: Seating,
+    generation: usize,
+    len: usize,
+    outcome: Outcome,
 }
 
 impl Iterator for SeatingIterator {
     type Item = Seating;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current != self.next {
-            self.current = self.next.clone();
-            self.next = self.current.next_state();
-            Some(self.next.clone())
-        } else {
-            None
+        if self.generation == self.len {
+            return None;
+        }
+        if self.generation > 0 {
+            self.current.next_state();
         }
+        self.generation += 1;
+
+        Some(self.current.clone())
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
 struct Point(usize, usize);
 
 impl Point {
@@ -154,41 +384,135 @@ impl Iterator for PointIterator {
     }
 }
 
-fn parse_input(input: &str) -> Data {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(row, line)| {
-            line.chars()
-                .enumerate()
-                .filter(|(_, c)| *c == 'L')
-                .map(move |(col, _)| (Point(row, col), Cell::Empty))
-        })
-        .collect::<HashMap<_, _>>()
+/// Reads `L` as an empty seat, `#` as an occupied one and `.` as floor.
+/// Every row has to be as long as the first one.
+fn parse_input(input: &str) -> Result<Data, ParseError> {
+    let lines: Vec<&str> = input.trim_end().lines().collect();
+    let width = lines.first().map_or(0, |line| line.chars().count());
+    let mut seats = HashMap::new();
+
+    for (row, line) in lines.iter().enumerate() {
+        let len = line.chars().count();
+        if len != width {
+            return Err(ParseError::Length { row, len, width });
+        }
+
+        for (col, c) in line.chars().enumerate() {
+            let point = Point(row, col);
+            match c {
+                'L' => seats.insert(point, Cell::Empty),
+                '#' => seats.insert(point, Cell::Occupied),
+                '.' => None,
+                c => return Err(ParseError::Char(c, point)),
+            };
+        }
+    }
+
+    Ok(Map {
+        width,
+        height: lines.len(),
+        seats,
+    })
 }
 
 fn solve_a(data: &Data) -> Solution {
-    let seating = Seating::new(data, 4, false);
+    let mut seating = Seating::new(data, Rules::part_a());
+    while seating.next_state() {}
 
-    seating
-        .into_iter()
-        .last()
-        .map(|seating| seating.count_occupied())
-        .unwrap_or(0)
+    seating.count_occupied()
 }
 
 fn solve_b(data: &Data) -> Solution {
-    let seating = Seating::new(data, 5, true);
+    let mut seating = Seating::new(data, Rules::part_b());
+    while seating.next_state() {}
+
+    seating.count_occupied()
+}
+
+const USAGE: &str = "\
+usage: day11 [--part a|b] [--birth N] [--survival N] [--view N]
+             [--text FILE] [--ppm DIR] [--scale N]
+
+Runs the seating with the rules of either part (a by default), optionally
+changed: an empty seat is taken when at most --birth visible seats are
+occupied, an occupied seat is left when at least --survival are, and seats
+look at most --view tiles far. Prints how the seating ended up.
+
+--text writes every generation to a single text file, --ppm writes
+DIR/generation-0000.ppm and so on, with every tile drawn as N by N pixels
+(4 by default).";
+
+fn run_custom(data: &Data, args: &[String]) -> io::Result<()> {
+    let mut rules = Rules::part_a();
+    let mut text = None;
+    let mut ppm = None;
+    let mut scale = 4;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().or_usage(USAGE);
+        match arg.as_str() {
+            "--part" => {
+                rules = match value().as_str() {
+                    "a" => Rules::part_a(),
+                    "b" => Rules::part_b(),
+                    _ => usage_error(USAGE),
+                }
+            }
+            "--birth" => rules.birth = value().parse().or_usage(USAGE),
+            "--survival" => rules.survival = value().parse().or_usage(USAGE),
+            "--view" => rules.view_distance = Some(value().parse().or_usage(USAGE)),
+            "--text" => text = Some(value().clone()),
+            "--ppm" => ppm = Some(value().clone()),
+            "--scale" => scale = value().parse().or_usage(USAGE),
+            _ => usage_error(USAGE),
+        }
+    }
+
+    let generations = Seating::new(data, rules).into_iter();
+    let outcome = generations.outcome;
+    let states: Vec<Seating> = generations.collect();
+
+    if let Some(path) = text {
+        frames::write_text(&states, &mut BufWriter::new(File::create(path)?))?;
+    }
+    if let Some(dir) = ppm {
+        fs::create_dir_all(&dir)?;
+        for (generation, seating) in states.iter().enumerate() {
+            let path = format!("{}/generation-{:04}.ppm", dir, generation);
+            frames::write_ppm(seating, scale, &mut BufWriter::new(File::create(path)?))?;
+        }
+    }
+
+    let occupied = states.last().map_or(0, |seating| seating.count_occupied());
+    match outcome {
+        Outcome::Settled { generation } => println!(
+            "settled after {} generations with {} occupied seats",
+            generation, occupied
+        ),
+        Outcome::Cycle { start, period } => println!(
+            "repeats every {} generations from generation {} on",
+            period, start
+        ),
+    }
 
-    seating
-        .into_iter()
-        .last()
-        .map(|seating| seating.count_occupied())
-        .unwrap_or(0)
+    Ok(())
 }
 
 fn main() {
-    let data = parse_input(INPUT);
+    let data = parse_input(INPUT).unwrap_or_else(|error| {
+        eprintln!("invalid input: {}", error);
+        std::process::exit(1);
+    });
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if !args.is_empty() {
+        if let Err(error) = run_custom(&data, &args) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     println!("Part A: {}", solve_a(&data));
     println!("Part B: {}", solve_b(&data));
@@ -199,7 +523,7 @@ mod tests {
     #[allow(unused_imports)]
     use super::*;
 
-    const EXAMPLE: &'static str = "L.LL.LL.LL
+    const EXAMPLE: &str = "L.LL.LL.LL
 LLLLLLL.LL
 L.L.L..L..
 LLLL.LL.LL
@@ -212,15 +536,131 @@ L.LLLLL.LL";
 
     #[test]
     fn examples_a() {
-        let data = parse_input(EXAMPLE);
+        let data = parse_input(EXAMPLE).unwrap();
 
         assert_eq!(solve_a(&data), 37);
     }
 
     #[test]
     fn examples_b() {
-        let data = parse_input(EXAMPLE);
+        let data = parse_input(EXAMPLE).unwrap();
 
         assert_eq!(solve_b(&data), 26);
     }
+
+    #[test]
+    fn visibility() {
+        let map = parse_input(
+            ".......#.
+...#.....
+.#.......
+.........
+..#L....#
+....#....
+.........
+#........
+...#.....",
+        )
+        .unwrap();
+        let seat = 4;
+
+        let seating = Seating::new(&map, Rules::part_b());
+        assert_eq!(seating.layout.seats[seat], Point(4, 3));
+        assert_eq!(seating.count_neighbours(seat), 8);
+
+        let seating = Seating::new(&map, Rules::part_a());
+        assert_eq!(seating.layout.visible(seat), &[3, 6]);
+    }
+
+    #[test]
+    fn rules() {
+        let data = parse_input("L...L").unwrap();
+        let sees_other = |view_distance| {
+            let rules = Rules {
+                view_distance,
+                ..Rules::part_b()
+            };
+            Seating::new(&data, rules).layout.visible(0).len() == 1
+        };
+
+        assert!(!sees_other(Some(3)));
+        assert!(sees_other(Some(4)));
+        assert!(sees_other(None));
+
+        let (seating, outcome) =
+            Seating::new(&parse_input(EXAMPLE).unwrap(), Rules::part_a()).settle();
+        assert_eq!(outcome, Outcome::Settled { generation: 5 });
+        assert_eq!(seating.count_occupied(), 37);
+
+        // Two seats that can never both stay taken.
+        let rules = Rules {
+            survival: 1,
+            ..Rules::part_a()
+        };
+        let (seating, outcome) = Seating::new(&parse_input("LL").unwrap(), rules).settle();
+        assert_eq!(
+            outcome,
+            Outcome::Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+        assert_eq!(seating.to_string(), "##");
+
+        let rules = Rules {
+            birth: 1,
+            ..Rules::part_a()
+        };
+        let (seating, _) = Seating::new(&parse_input("LLL").unwrap(), rules).settle();
+        assert_eq!(seating.to_string(), "###");
+    }
+
+    #[test]
+    fn late_cycle() {
+        // Both taken seats are left, and from then on all seats blink.
+        let rules = Rules {
+            survival: 1,
+            ..Rules::part_a()
+        };
+        let seating = Seating::new(&parse_input("##L").unwrap(), rules);
+        let (last, outcome) = seating.clone().settle();
+        let states: Vec<String> = seating.into_iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(
+            outcome,
+            Outcome::Cycle {
+                start: 1,
+                period: 2
+            }
+        );
+        assert_eq!(last.to_string(), "###");
+        assert_eq!(states, vec!["##L", "LLL", "###"]);
+    }
+
+    #[test]
+    fn parsing() {
+        let map = parse_input("L#..\n....\n..L.\n").unwrap();
+
+        assert_eq!((map.width, map.height), (4, 3));
+        assert_eq!(map.seats[&Point(0, 1)], Cell::Occupied);
+        assert_eq!(
+            Seating::new(&map, Rules::part_a()).to_string(),
+            "L#..\n....\n..L."
+        );
+
+        let error = parse_input("L.L\nL.x").unwrap_err();
+        assert_eq!(error, ParseError::Char('x', Point(1, 2)));
+        assert_eq!(error.to_string(), "unexpected 'x' at line 2, column 3");
+
+        let error = parse_input("L#..\n....\n..").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::Length {
+                row: 2,
+                len: 2,
+                width: 4
+            }
+        );
+        assert_eq!(error.to_string(), "line 3 has 2 tiles, expected 4");
+    }
 }
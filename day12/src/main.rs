@@ -1,24 +1,86 @@
 #[allow(unused_imports)]
 use shared::prelude::*;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 
-const INPUT: &'static str = include_str!("./input.txt");
+mod svg;
+
+const INPUT: &str = include_str!("./input.txt");
 
 type Position = (isize, isize);
 type Direction = (isize, isize);
-type Command = (char, isize);
 type Data = Vec<Command>;
 type Solution = usize;
 
-fn parse_input(input: &str) -> Data {
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    /// `N`, `S`, `E` or `W`: move the ship (or waypoint) without turning.
+    Move(Direction, isize),
+    /// `L` or `R`, as a number of clockwise quarter turns between 0 and 3.
+    Turn(u8),
+    /// `F`: move along the heading (or towards the waypoint).
+    Forward(isize),
+}
+
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    Action(usize, char),
+    Value(usize, String),
+    Angle(usize, isize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Action(line, c) => write!(f, "line {}: unknown action '{}'", line + 1, c),
+            ParseError::Value(line, value) => {
+                write!(f, "line {}: '{}' is not a number", line + 1, value)
+            }
+            ParseError::Angle(line, angle) => write!(
+                f,
+                "line {}: can only turn by multiples of 90 degrees, not {}",
+                line + 1,
+                angle
+            ),
+        }
+    }
+}
+
+fn parse_command(line_idx: usize, line: &str) -> Result<Command, ParseError> {
+    let mut chars = line.chars();
+    let action = chars.next().unwrap_or(' ');
+    let value: isize = chars
+        .as_str()
+        .parse()
+        .map_err(|_| ParseError::Value(line_idx, chars.as_str().to_string()))?;
+
+    let quarter_turns = || {
+        if value % 90 == 0 {
+            Ok((value / 90).rem_euclid(4) as u8)
+        } else {
+            Err(ParseError::Angle(line_idx, value))
+        }
+    };
+
+    match action {
+        'N' => Ok(Command::Move((0, 1), value)),
+        'S' => Ok(Command::Move((0, -1), value)),
+        'E' => Ok(Command::Move((1, 0), value)),
+        'W' => Ok(Command::Move((-1, 0), value)),
+        'R' => Ok(Command::Turn(quarter_turns()?)),
+        'L' => Ok(Command::Turn((4 - quarter_turns()?) % 4)),
+        'F' => Ok(Command::Forward(value)),
+        c => Err(ParseError::Action(line_idx, c)),
+    }
+}
+
+fn parse_input(input: &str) -> Result<Data, ParseError> {
     input
         .lines()
-        .map(|line| {
-            let (action, value) = line.split_at(1);
-            let action = action.chars().next().unwrap();
-            let value = value.parse().unwrap_or(0);
-
-            (action, value)
-        })
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| parse_command(idx, line.trim()))
         .collect()
 }
 
@@ -26,56 +88,170 @@ fn manhattan_distance((x, y): Position) -> usize {
     (x.abs() + y.abs()) as usize
 }
 
-fn rotate((x, y): Direction, angle: isize) -> Direction {
-    match angle {
-        90 => (y, -x),
-        180 => (-x, -y),
-        270 => (-y, x),
-        _ => (x, y),
+/// Turns clockwise by `quarter_turns` times 90 degrees.
+fn rotate(direction: Direction, quarter_turns: u8) -> Direction {
+    (0..quarter_turns).fold(direction, |(x, y), _| (y, -x))
+}
+
+/// What the vector next to the ship means.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Navigation {
+    /// Part A: the ship's heading, moved by `N`/`S`/`E`/`W` commands.
+    Heading,
+    /// Part B: the waypoint relative to the ship, moved by `N`/`S`/`E`/`W`
+    /// commands.
+    Waypoint,
+}
+
+impl Navigation {
+    fn start(self) -> Direction {
+        match self {
+            Navigation::Heading => (1, 0),
+            Navigation::Waypoint => (10, 1),
+        }
+    }
+}
+
+/// The ship's position and its heading or waypoint after a command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub ship: Position,
+    pub vector: Direction,
+}
+
+impl Step {
+    /// Where the waypoint is, when `vector` is the waypoint.
+    pub fn waypoint(&self) -> Position {
+        (self.ship.0 + self.vector.0, self.ship.1 + self.vector.1)
+    }
+
+    fn step(self, navigation: Navigation, command: Command) -> Step {
+        let Step {
+            ship: (x, y),
+            vector: (dx, dy),
+        } = self;
+
+        match (command, navigation) {
+            (Command::Forward(value), _) => Step {
+                ship: (x + dx * value, y + dy * value),
+                ..self
+            },
+            (Command::Move((mx, my), value), Navigation::Heading) => Step {
+                ship: (x + mx * value, y + my * value),
+                ..self
+            },
+            (Command::Move((mx, my), value), Navigation::Waypoint) => Step {
+                vector: (dx + mx * value, dy + my * value),
+                ..self
+            },
+            (Command::Turn(quarter_turns), _) => Step {
+                vector: rotate(self.vector, quarter_turns),
+                ..self
+            },
+        }
     }
 }
 
-fn solve_a(data: &Data) -> Solution {
-    let (_, position) = data.iter().fold(
-        ((1, 0), (0, 0)),
-        |((dx, dy), (x, y)), (action, value)| match action {
-            'F' => ((dx, dy), (x + dx * value, y + dy * value)),
-            'N' => ((dx, dy), (x, y + value)),
-            'S' => ((dx, dy), (x, y - value)),
-            'E' => ((dx, dy), (x + value, y)),
-            'W' => ((dx, dy), (x - value, y)),
-            'R' => (rotate((dx, dy), *value), (x, y)),
-            'L' => (rotate((dx, dy), 360 - value), (x, y)),
-            _ => ((dx, dy), (x, y)),
-        },
-    );
-
-    manhattan_distance(position)
-}
-
-fn solve_b(data: &Data) -> Solution {
-    let (_, position) = data.iter().fold(
-        ((10, 1), (0, 0)),
-        |((dx, dy), (x, y)), (action, value)| match action {
-            'F' => ((dx, dy), (x + dx * value, y + dy * value)),
-            'N' => ((dx, dy + value), (x, y)),
-            'S' => ((dx, dy - value), (x, y)),
-            'E' => ((dx + value, dy), (x, y)),
-            'W' => ((dx - value, dy), (x, y)),
-            'R' => (rotate((dx, dy), *value), (x, y)),
-            'L' => (rotate((dx, dy), 360 - value), (x, y)),
-            _ => ((dx, dy), (x, y)),
-        },
-    );
-
-    manhattan_distance(position)
+/// Every step of the route, starting at the origin before the first command.
+fn trace(data: &[Command], navigation: Navigation) -> Vec<Step> {
+    let start = Step {
+        ship: (0, 0),
+        vector: navigation.start(),
+    };
+
+    std::iter::once(start)
+        .chain(data.iter().scan(start, |step, &command| {
+            *step = step.step(navigation, command);
+            Some(*step)
+        }))
+        .collect()
+}
+
+fn navigate(data: &[Command], navigation: Navigation) -> Solution {
+    let end = trace(data, navigation)
+        .last()
+        .map_or((0, 0), |step| step.ship);
+
+    manhattan_distance(end)
+}
+
+fn solve_a(data: &[Command]) -> Solution {
+    navigate(data, Navigation::Heading)
+}
+
+fn solve_b(data: &[Command]) -> Solution {
+    navigate(data, Navigation::Waypoint)
+}
+
+const USAGE: &str = "\
+usage: day12 [--part a|b] [--path] [--svg FILE]
+
+Traces the ferry's route. --path prints the ship position and the heading
+(part a) or waypoint (part b) after every command, --svg draws the route.";
+
+fn run(args: &[String], data: &[Command]) -> io::Result<()> {
+    let mut navigation = Navigation::Heading;
+    let mut path = false;
+    let mut svg_file = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().or_usage(USAGE);
+        match arg.as_str() {
+            "--part" => {
+                navigation = match value().as_str() {
+                    "a" | "A" => Navigation::Heading,
+                    "b" | "B" => Navigation::Waypoint,
+                    _ => usage_error(USAGE),
+                }
+            }
+            "--path" => path = true,
+            "--svg" => svg_file = Some(value().clone()),
+            _ => usage_error(USAGE),
+        }
+    }
+
+    let steps = trace(data, navigation);
+    if path {
+        match navigation {
+            Navigation::Heading => println!("step,x,y,heading_dx,heading_dy"),
+            Navigation::Waypoint => println!("step,x,y,waypoint_x,waypoint_y"),
+        }
+        for (idx, step) in steps.iter().enumerate() {
+            let (vx, vy) = match navigation {
+                Navigation::Heading => step.vector,
+                Navigation::Waypoint => step.waypoint(),
+            };
+            println!("{},{},{},{},{}", idx, step.ship.0, step.ship.1, vx, vy);
+        }
+    }
+    if let Some(svg_file) = svg_file {
+        let mut out = BufWriter::new(File::create(&svg_file)?);
+        svg::render(&steps, navigation, &mut out)?;
+        out.flush()?;
+    }
+
+    let end = steps.last().map_or((0, 0), |step| step.ship);
+    println!("{}", manhattan_distance(end));
+
+    Ok(())
 }
 
 fn main() {
-    let data = parse_input(INPUT);
+    let data = parse_input(INPUT).unwrap_or_else(|error| {
+        eprintln!("invalid input: {}", error);
+        std::process::exit(1);
+    });
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    println!("Part A: {}", solve_a(&data));
-    println!("Part B: {}", solve_b(&data));
+    if args.is_empty() {
+        println!("Part A: {}", solve_a(&data));
+        println!("Part B: {}", solve_b(&data));
+    } else if let Err(error) = run(&args, &data) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
 }
 
 #[cfg(test)]
@@ -83,7 +259,7 @@ mod tests {
     #[allow(unused_imports)]
     use super::*;
 
-    const EXAMPLE: &'static str = "F10
+    const EXAMPLE: &str = "F10
 N3
 F7
 R90
@@ -91,13 +267,61 @@ F11";
 
     #[test]
     fn examples_a() {
-        let data = parse_input(EXAMPLE);
+        let data = parse_input(EXAMPLE).unwrap();
         assert_eq!(solve_a(&data), 25);
     }
 
     #[test]
     fn examples_b() {
-        let data = parse_input(EXAMPLE);
+        let data = parse_input(EXAMPLE).unwrap();
         assert_eq!(solve_b(&data), 286);
     }
+
+    #[test]
+    fn rotations() {
+        let turns = parse_input("R90\nL90\nR-90\nL450\nR360\nL0")
+            .unwrap()
+            .into_iter()
+            .map(|command| match command {
+                Command::Turn(quarter_turns) => quarter_turns,
+                command => panic!("{:?}", command),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(turns, vec![1, 3, 3, 3, 0, 0]);
+        assert_eq!(rotate((10, 4), 1), (4, -10));
+        assert_eq!(rotate((10, 4), 3), (-4, 10));
+
+        assert_eq!(parse_input("F10\nR45"), Err(ParseError::Angle(1, 45)));
+        assert_eq!(parse_input("X10"), Err(ParseError::Action(0, 'X')));
+        assert_eq!(
+            parse_input("F").unwrap_err().to_string(),
+            "line 1: '' is not a number"
+        );
+    }
+
+    #[test]
+    fn paths() {
+        let data = parse_input(EXAMPLE).unwrap();
+
+        let ships: Vec<Position> = trace(&data, Navigation::Heading)
+            .iter()
+            .map(|step| step.ship)
+            .collect();
+        assert_eq!(
+            ships,
+            vec![(0, 0), (10, 0), (10, 3), (17, 3), (17, 3), (17, -8)]
+        );
+
+        let steps = trace(&data, Navigation::Waypoint);
+        assert_eq!(steps.len(), 6);
+        assert_eq!(
+            steps[4],
+            Step {
+                ship: (170, 38),
+                vector: (4, -10)
+            }
+        );
+        assert_eq!(steps[5].ship, (214, -72));
+        assert_eq!(steps[5].waypoint(), (218, -82));
+    }
 }
@@ -0,0 +1,108 @@
+use crate::{Navigation, Position, Step};
+use std::io::{self, Write};
+
+/// Space around the route, as a fraction of its larger side.
+const MARGIN: f64 = 0.05;
+
+/// The smallest and largest `x` and `y` among `points`.
+fn bounds(points: impl Iterator<Item = Position>) -> (Position, Position) {
+    points.fold(((0, 0), (0, 0)), |((x0, y0), (x1, y1)), (x, y)| {
+        ((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y)))
+    })
+}
+
+/// Draws the ship's track as a polyline from a green start to a red end.
+/// With `Navigation::Waypoint` every step also gets a line from the ship to
+/// its waypoint. North is up, so `y` is flipped.
+pub fn render(steps: &[Step], navigation: Navigation, out: &mut impl Write) -> io::Result<()> {
+    let waypoints = navigation == Navigation::Waypoint;
+    let ((x0, y0), (x1, y1)) = if waypoints {
+        bounds(
+            steps
+                .iter()
+                .flat_map(|step| vec![step.ship, step.waypoint()]),
+        )
+    } else {
+        bounds(steps.iter().map(|step| step.ship))
+    };
+    let size = (x1 - x0).max(y1 - y0).max(1) as f64;
+    let margin = size * MARGIN;
+    let radius = size * 0.01;
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        x0 as f64 - margin,
+        -y1 as f64 - margin,
+        (x1 - x0) as f64 + 2.0 * margin,
+        (y1 - y0) as f64 + 2.0 * margin
+    )?;
+    writeln!(out, r#"<g fill="none" stroke-width="1">"#)?;
+
+    if waypoints {
+        for step in steps {
+            let ((x, y), (wx, wy)) = (step.ship, step.waypoint());
+            writeln!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="lightgray" vector-effect="non-scaling-stroke"/>"#,
+                x, -y, wx, -wy
+            )?;
+        }
+    }
+
+    let points: Vec<String> = steps
+        .iter()
+        .map(|step| format!("{},{}", step.ship.0, -step.ship.1))
+        .collect();
+    writeln!(
+        out,
+        r#"<polyline points="{}" stroke="steelblue" vector-effect="non-scaling-stroke"/>"#,
+        points.join(" ")
+    )?;
+    writeln!(out, "</g>")?;
+
+    if let (Some(first), Some(last)) = (steps.first(), steps.last()) {
+        for &(step, colour) in &[(first, "green"), (last, "red")] {
+            writeln!(
+                out,
+                r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                step.ship.0, -step.ship.1, radius, colour
+            )?;
+        }
+    }
+
+    writeln!(out, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, trace};
+
+    #[test]
+    fn ship_track() {
+        let steps = trace(&parse_input("F10\nN5").unwrap(), Navigation::Heading);
+        let mut out = vec![];
+        render(&steps, Navigation::Heading, &mut out).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-0.5 -5.5 11 6">"#)
+        );
+        assert!(svg.contains(r#"<polyline points="0,0 10,0 10,-5""#));
+        assert!(!svg.contains("<line"));
+        assert!(svg.contains(r#"<circle cx="10" cy="-5" r="0.1" fill="red"/>"#));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn waypoint_vectors() {
+        let steps = trace(&parse_input("F1").unwrap(), Navigation::Waypoint);
+        let mut out = vec![];
+        render(&steps, Navigation::Waypoint, &mut out).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+
+        assert!(svg.contains(r#"<line x1="0" y1="0" x2="10" y2="-1""#));
+        assert!(svg.contains(r#"<line x1="10" y1="-1" x2="20" y2="-2""#));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::mask::{self, Mask};
+use crate::memory::FloatingMemory;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    SetMask(Mask),
+    Write { address: u64, value: u64 },
+}
+
+/// Which bits the mask is applied to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Version {
+    /// The mask overwrites bits of the value.
+    V1,
+    /// The mask turns the address into a set of floating addresses.
+    V2,
+}
+
+/// What a single `mem[…] = …` instruction wrote.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub instruction: usize,
+    pub address: u64,
+    pub value: u64,
+    pub written: Mask,
+    width: usize,
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>4}: mem[{}] = {} -> {} ({} addresses)",
+            self.instruction + 1,
+            self.address,
+            self.value,
+            self.written.format(self.width),
+            self.written.combinations()
+        )
+    }
+}
+
+/// Runs a docking program on a machine with `width` bit words. Addresses
+/// and values that don't fit in a word are cut to their lowest bits.
+pub struct Emulator {
+    version: Version,
+    width: usize,
+    mask: Mask,
+    memory: FloatingMemory,
+    executed: usize,
+    trace: Option<Vec<Trace>>,
+}
+
+impl Emulator {
+    pub fn new(version: Version, width: usize) -> Self {
+        assert!(
+            (1..=64).contains(&width),
+            "word width must be between 1 and 64 bits"
+        );
+
+        Emulator {
+            version,
+            width,
+            // Until a mask is set, v1 leaves values and v2 leaves addresses
+            // unchanged.
+            mask: match version {
+                Version::V1 => Mask {
+                    floating: mask::word(width),
+                    ..Mask::default()
+                },
+                Version::V2 => Mask {
+                    zeros: mask::word(width),
+                    ..Mask::default()
+                },
+            },
+            memory: FloatingMemory::new(),
+            executed: 0,
+            trace: None,
+        }
+    }
+
+    /// Records every write, see `trace`.
+    pub fn with_trace(self) -> Self {
+        Emulator {
+            trace: Some(vec![]),
+            ..self
+        }
+    }
+
+    pub fn execute(&mut self, instruction: &Instruction) {
+        match *instruction {
+            Instruction::SetMask(mask) => self.mask = mask,
+            Instruction::Write { address, value } => {
+                let word = mask::word(self.width);
+                let (address, value) = (address & word, value & word);
+                let (written, value) = match self.version {
+                    Version::V1 => (Mask::address(address, self.width), self.mask.apply(value)),
+                    Version::V2 => (self.mask.decode(address), value),
+                };
+
+                self.memory.write(written, value);
+                if let Some(trace) = &mut self.trace {
+                    trace.push(Trace {
+                        instruction: self.executed,
+                        address,
+                        value,
+                        written,
+                        width: self.width,
+                    });
+                }
+            }
+        }
+        self.executed += 1;
+    }
+
+    pub fn run(&mut self, program: &[Instruction]) {
+        for instruction in program {
+            self.execute(instruction);
+        }
+    }
+
+    pub fn memory(&self) -> &FloatingMemory {
+        &self.memory
+    }
+
+    /// The writes so far, if tracing was enabled.
+    pub fn trace(&self) -> Option<&[Trace]> {
+        self.trace.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrow_words() {
+        let program = [
+            Instruction::SetMask(Mask::parse("1X0X", 4).unwrap()),
+            Instruction::Write {
+                address: 3,
+                value: 7,
+            },
+            Instruction::Write {
+                address: 17,
+                value: 18,
+            },
+        ];
+
+        let mut v1 = Emulator::new(Version::V1, 4).with_trace();
+        v1.run(&program);
+        assert_eq!(v1.memory().read(3), 0b1101);
+        assert_eq!(v1.memory().read(1), 0b1000);
+        assert_eq!(v1.memory().sum(), Some(21));
+
+        let mut v2 = Emulator::new(Version::V2, 4).with_trace();
+        v2.run(&program);
+        let mut written: Vec<(u64, u64)> = v2.memory().iter().collect();
+        written.sort_unstable();
+        assert_eq!(
+            written,
+            vec![
+                (8, 2),
+                (9, 2),
+                (10, 7),
+                (11, 7),
+                (12, 2),
+                (13, 2),
+                (14, 7),
+                (15, 7)
+            ]
+        );
+
+        let trace: Vec<String> = v2.trace().unwrap().iter().map(|t| t.to_string()).collect();
+        assert_eq!(
+            trace,
+            vec![
+                "   2: mem[3] = 7 -> 1X1X (4 addresses)",
+                "   3: mem[1] = 2 -> 1X0X (4 addresses)",
+            ]
+        );
+    }
+
+    #[test]
+    fn without_mask() {
+        let program = [Instruction::Write {
+            address: 5,
+            value: 6,
+        }];
+
+        let mut v1 = Emulator::new(Version::V1, 36);
+        v1.run(&program);
+        assert_eq!(v1.memory().read(5), 6);
+        assert_eq!(v1.trace(), None);
+
+        let mut v2 = Emulator::new(Version::V2, 36);
+        v2.run(&program);
+        assert_eq!(v2.memory().iter().collect::<Vec<_>>(), vec![(5, 6)]);
+    }
+
+    #[test]
+    fn all_bits_floating() {
+        let program = [
+            Instruction::SetMask(Mask::parse(&"X".repeat(64), 64).unwrap()),
+            Instruction::Write {
+                address: 0,
+                value: 1,
+            },
+        ];
+
+        let mut v2 = Emulator::new(Version::V2, 64).with_trace();
+        v2.run(&program);
+        assert_eq!(v2.memory().len(), 1 << 64);
+        assert_eq!(v2.memory().sum(), Some(1 << 64));
+        assert!(v2.trace().unwrap()[0]
+            .to_string()
+            .ends_with("(18446744073709551616 addresses)"));
+    }
+}
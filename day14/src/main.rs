@@ -1,21 +1,23 @@
 #![feature(str_split_once)]
+mod emulator;
+mod mask;
+mod memory;
+
+use emulator::{Emulator, Instruction, Version};
+use mask::{Mask, MaskError};
 #[allow(unused_imports)]
 use shared::prelude::*;
 
-const INPUT: &'static str = include_str!("./input.txt");
-
-type Data<'a> = Vec<(Vec<char>, Vec<(u64, u64)>)>;
-type Solution = u64;
+const INPUT: &str = include_str!("./input.txt");
 
-fn parse_mask(line: &str) -> Vec<char> {
-    line.trim_start_matches("mask = ").chars().rev().collect()
-}
+type Data = Vec<Instruction>;
+type Solution = u128;
 
 fn parse_assignment(line: &str) -> (u64, u64) {
     let (target, value) = line.split_once(" = ").unwrap();
     let target = target
         .trim_start_matches("mem[")
-        .trim_end_matches("]")
+        .trim_end_matches(']')
         .parse()
         .unwrap();
     let value = value.parse().unwrap();
@@ -23,129 +25,146 @@ fn parse_assignment(line: &str) -> (u64, u64) {
     (target, value)
 }
 
-fn parse_input(input: &str) -> Data {
-    let mut lines = input.lines();
-    let mut current_mask = parse_mask(lines.next().unwrap());
-    let mut assignments = vec![];
-    let mut result = vec![];
-
-    for line in lines {
-        if line.starts_with("mask = ") {
-            result.push((current_mask, assignments));
-            assignments = vec![];
-            current_mask = parse_mask(line);
+fn parse_program(input: &str, width: usize) -> Result<Data, MaskError> {
+    let mut program = vec![];
+
+    for line in input.lines() {
+        if let Some(mask) = line.strip_prefix("mask = ") {
+            program.push(Instruction::SetMask(Mask::parse(mask, width)?));
         } else if line.starts_with("mem[") {
-            let assignment = parse_assignment(line);
-            assignments.push(assignment);
+            let (address, value) = parse_assignment(line);
+            program.push(Instruction::Write { address, value });
         }
     }
-    result.push((current_mask, assignments));
 
-    result
+    Ok(program)
 }
 
-fn apply_mask(mask: &Vec<char>, value: u64) -> u64 {
-    mask.iter().enumerate().fold(0, |acc, (idx, c)| match *c {
-        '1' => acc | 1 << idx,
-        'X' => acc | ((value >> idx) & 1) << idx,
-        _ => acc,
-    })
+fn parse_input(input: &str) -> Result<Data, MaskError> {
+    parse_program(input, mask::WIDTH)
 }
 
-fn solve_a(data: &Data) -> Solution {
-    let mut mem = HashMap::with_capacity(data.iter().map(|(_, ass)| ass.len()).sum());
-
-    for (mask, assignments) in data {
-        for (target, value) in assignments {
-            mem.insert(*target, apply_mask(mask, *value));
-        }
+/// Runs a parsed program on a `width` bit machine, recording every write
+/// if `trace` is set.
+fn emulate(program: &[Instruction], version: Version, width: usize, trace: bool) -> Emulator {
+    let mut emulator = Emulator::new(version, width);
+    if trace {
+        emulator = emulator.with_trace();
     }
+    emulator.run(program);
 
-    mem.values().sum()
+    emulator
 }
 
-fn apply_memmask(mask: &Vec<char>, target: u64) -> Vec<char> {
-    mask.iter()
-        .enumerate()
-        .map(|(idx, c)| match c {
-            '1' => '1',
-            '0' => {
-                if ((target >> idx) & 1) == 1 {
-                    '1'
-                } else {
-                    '0'
-                }
-            }
-            c => *c,
-        })
-        .collect()
+fn run(data: &[Instruction], version: Version) -> Emulator {
+    emulate(data, version, mask::WIDTH, false)
 }
 
-fn has_overlap(source: &Vec<char>, target: &Vec<char>) -> bool {
-    !source
-        .iter()
-        .zip(target.iter())
-        .any(|(sc, tc)| *tc != 'X' && *sc != 'X' && *tc != *sc)
+fn solve_a(data: &[Instruction]) -> Solution {
+    run(data, Version::V1).memory().sum().unwrap()
 }
 
-fn purge_overlap(source: &Vec<char>, target: &Vec<char>) -> Vec<Vec<char>> {
-    // find first overlap
-    match source
-        .iter()
-        .zip(target.iter())
-        .find_position(|(sc, tc)| **tc == 'X' && **sc != 'X')
-    {
-        Some((idx, (sc, _))) => {
-            let mut first = target.clone();
-            first[idx] = if *sc == '1' { '0' } else { '1' };
-
-            let (head, tail) = target.split_at(idx);
-            let mut tail = tail.to_vec();
-            tail[0] = *sc;
-            let mut source_tail = source.clone();
-            source_tail.drain(0..idx);
-
-            purge_overlap(&source_tail, &tail)
-                .into_iter()
-                .map(|tail| head.iter().chain(&tail).map(|c| *c).collect())
-                .chain(std::iter::once(first))
-                .collect()
-        }
-        None => vec![],
-    }
+fn solve_b(data: &[Instruction]) -> Solution {
+    run(data, Version::V2).memory().sum().unwrap()
 }
 
-fn solve_b(data: &Data) -> Solution {
-    let mut memmasks: Vec<(Vec<char>, u64)> = Vec::new();
-
-    for (mask, assignments) in data {
-        for (target, value) in assignments {
-            let source_mask = apply_memmask(mask, *target);
-            memmasks = memmasks
-                .into_iter()
-                .flat_map(|(target_mask, value)| {
-                    if has_overlap(&source_mask, &target_mask) {
-                        purge_overlap(&source_mask, &target_mask)
-                            .into_iter()
-                            .map(|mask| (mask, value))
-                            .collect::<Vec<(Vec<char>, u64)>>()
-                    } else {
-                        vec![(target_mask, value)]
-                    }
-                })
-                .collect();
-            memmasks.push((source_mask, *value))
+const USAGE: &str = "\
+usage: day14 [--program FILE] [--decoder 1|2] [--width BITS] [--trace]
+             [--read ADDRESS... | --dump]
+
+Runs the program (the puzzle input by default) with a single decoder
+(version 2 by default) on a machine with BITS bit words (36 by default), and
+prints the sum of the memory. --trace lists the addresses every instruction wrote to,
+--read prints the values at the given addresses and --dump prints every
+written address.";
+
+fn run_custom(args: &[String]) {
+    let mut path = None;
+    let mut version = Version::V2;
+    let mut width = mask::WIDTH;
+    let mut trace = false;
+    let mut reads: Option<Vec<u64>> = None;
+    let mut dump = false;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--decoder" => {
+                version = match args.next().map(|v| v.as_str()) {
+                    Some("1") => Version::V1,
+                    Some("2") => Version::V2,
+                    _ => usage_error(USAGE),
+                }
+            }
+            "--width" => {
+                width = args
+                    .next()
+                    .and_then(|w| w.parse().ok())
+                    .filter(|w| (1..=64).contains(w))
+                    .or_usage(USAGE)
+            }
+            "--program" => path = Some(args.next().or_usage(USAGE)),
+            "--trace" => trace = true,
+            "--dump" => dump = true,
+            "--read" => {
+                reads = Some(
+                    args.by_ref()
+                        .map(|address| address.parse().or_usage(USAGE))
+                        .collect(),
+                )
+            }
+            _ => usage_error(USAGE),
         }
     }
 
-    memmasks
-        .iter()
-        .map(|(mask, value)| *value * 2u64.pow(mask.iter().filter(|c| **c == 'X').count() as u32))
-        .sum()
+    let input = match path {
+        Some(path) => std::fs::read_to_string(path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(1);
+        }),
+        None => INPUT.to_string(),
+    };
+    let program = parse_program(&input, width).unwrap_or_else(|error| {
+        eprintln!("invalid input: {}", error);
+        std::process::exit(1);
+    });
+    let emulator = emulate(&program, version, width, trace);
+
+    for step in emulator.trace().unwrap_or(&[]) {
+        println!("{}", step);
+    }
+
+    let memory = emulator.memory();
+    if let Some(reads) = reads {
+        for address in reads {
+            println!("mem[{}] = {}", address, memory.read(address));
+        }
+    } else if dump {
+        let mut pairs: Vec<(u64, u64)> = memory.iter().collect();
+        pairs.sort_unstable();
+
+        println!("{} addresses written", memory.len());
+        for (address, value) in pairs {
+            println!("mem[{}] = {}", address, value);
+        }
+    } else {
+        match memory.sum() {
+            Some(sum) => println!("{}", sum),
+            None => println!("the sum doesn't fit in 128 bits"),
+        }
+    }
 }
 
 fn main() {
-    let data = parse_input(INPUT);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return run_custom(&args);
+    }
+
+    let data = parse_input(INPUT).unwrap_or_else(|error| {
+        eprintln!("invalid input: {}", error);
+        std::process::exit(1);
+    });
 
     println!("Part A: {}", solve_a(&data));
     println!("Part B: {}", solve_b(&data));
@@ -162,7 +181,8 @@ mod tests {
 mem[8] = 11
 mem[7] = 101
 mem[8] = 0",
-        );
+        )
+        .unwrap();
 
         assert_eq!(solve_a(&data), 165);
     }
@@ -174,42 +194,35 @@ mem[8] = 0",
 mem[42] = 100
 mask = 00000000000000000000000000000000X0XX
 mem[26] = 1",
-        );
+        )
+        .unwrap();
         assert_eq!(solve_b(&data), 208);
     }
 
     #[test]
-    fn overlap() {
-        let mask1 = vec!['X', 'X', 'X', '1', '1', 'X'];
-        let mask2 = vec!['1', '1', '0', 'X', 'X', 'X'];
-
-        let purged_masks = purge_overlap(&mask2, &mask1);
-
+    fn invalid_masks() {
         assert_eq!(
-            purged_masks,
-            vec![
-                vec!['1', '1', '1', '1', '1', 'X',],
-                vec!['1', '0', 'X', '1', '1', 'X',],
-                vec!['0', 'X', 'X', '1', '1', 'X',],
-            ]
-        )
+            parse_input("mask = X1001X\nmem[42] = 100").unwrap_err(),
+            MaskError::Length(6, 36)
+        );
+        assert_eq!(
+            parse_input("mask = 000000000000000000000000000000X1001X\nmask = 00000000000000000000000000000000X0Y1")
+                .unwrap_err()
+                .to_string(),
+            "unexpected 'Y' at position 35 of mask"
+        );
     }
 
     #[test]
-    fn fully_covered() {
-        let mask1 = vec!['X', 'X', 'X', '1', '1', 'X'];
-        let mask2 = vec!['X', 'X', 'X', '1', '1', 'X'];
+    fn narrow_program() {
+        let program = parse_program("mask = 1X0X\nmem[3] = 7\nmem[17] = 18", 4).unwrap();
 
-        let purged_masks = purge_overlap(&mask2, &mask1);
-
-        assert_eq!(purged_masks, Vec::<Vec<char>>::new());
-    }
-
-    #[test]
-    fn no_overlap() {
-        let mask1 = vec!['X', 'X', '0', '1', '1', 'X'];
-        let mask2 = vec!['X', 'X', '1', '1', '1', 'X'];
+        let v1 = emulate(&program, Version::V1, 4, false);
+        assert_eq!(v1.memory().sum(), Some(0b1101 + 0b1000));
 
-        assert_eq!(has_overlap(&mask2, &mask1), false);
+        let v2 = emulate(&program, Version::V2, 4, true);
+        assert_eq!(v2.memory().sum(), Some(4 * 7 + 4 * 2));
+        assert_eq!(v2.trace().map(|trace| trace.len()), Some(2));
+        assert!(parse_program("mask = 1X0X", 36).is_err());
     }
 }
@@ -0,0 +1,277 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// The word width of the docking program in the puzzle.
+pub const WIDTH: usize = 36;
+
+#[derive(Debug, PartialEq)]
+pub enum MaskError {
+    /// The mask's length and the expected word width.
+    Length(usize, usize),
+    Char(char, usize),
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaskError::Length(len, width) => {
+                write!(f, "mask has {} bits, expected {}", len, width)
+            }
+            MaskError::Char(c, idx) => {
+                write!(f, "unexpected '{}' at position {} of mask", c, idx + 1)
+            }
+        }
+    }
+}
+
+/// A mask like `X1001X`, split into the bits it forces to 1, the bits it
+/// forces to 0 and the bits it leaves floating. Every bit within the width
+/// is in exactly one of them.
+///
+/// The same type describes a set of addresses: the fixed bits are shared by
+/// all of them and the floating bits take every combination.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mask {
+    pub ones: u64,
+    pub zeros: u64,
+    pub floating: u64,
+}
+
+/// All bits of a `width` bit word set.
+pub fn word(width: usize) -> u64 {
+    if width >= 64 {
+        !0
+    } else {
+        (1 << width) - 1
+    }
+}
+
+impl FromStr for Mask {
+    type Err = MaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mask::parse(s, WIDTH)
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(WIDTH))
+    }
+}
+
+impl Mask {
+    /// Parses a mask for a `width` bit word, most significant bit first.
+    pub fn parse(s: &str, width: usize) -> Result<Self, MaskError> {
+        let len = s.chars().count();
+        if len != width || width > 64 {
+            return Err(MaskError::Length(len, width));
+        }
+
+        s.chars()
+            .enumerate()
+            .try_fold(Mask::default(), |mask, (idx, c)| {
+                let bit = 1 << (width - 1 - idx);
+                match c {
+                    '1' => Ok(Mask {
+                        ones: mask.ones | bit,
+                        ..mask
+                    }),
+                    '0' => Ok(Mask {
+                        zeros: mask.zeros | bit,
+                        ..mask
+                    }),
+                    'X' => Ok(Mask {
+                        floating: mask.floating | bit,
+                        ..mask
+                    }),
+                    c => Err(MaskError::Char(c, idx)),
+                }
+            })
+    }
+
+    /// The set holding just `address`, within a `width` bit word.
+    pub fn address(address: u64, width: usize) -> Self {
+        Mask {
+            ones: address,
+            zeros: word(width) & !address,
+            floating: 0,
+        }
+    }
+
+    /// The lowest `width` bits of the mask, like it would be written in a
+    /// program.
+    pub fn format(&self, width: usize) -> String {
+        (0..width)
+            .rev()
+            .map(|idx| match 1 << idx {
+                bit if self.ones & bit != 0 => '1',
+                bit if self.floating & bit != 0 => 'X',
+                _ => '0',
+            })
+            .collect()
+    }
+
+    fn fixed(&self) -> u64 {
+        self.ones | self.zeros
+    }
+
+    /// Version 1 decoding: overwrites the value's bits with the fixed bits.
+    pub fn apply(&self, value: u64) -> u64 {
+        value & self.floating | self.ones
+    }
+
+    /// Version 2 decoding: the set of addresses written to when writing to
+    /// `address`. Ones overwrite the address, zeros leave it unchanged.
+    pub fn decode(&self, address: u64) -> Mask {
+        let ones = (address | self.ones) & !self.floating;
+
+        Mask {
+            ones,
+            zeros: self.fixed() & !ones,
+            floating: self.floating,
+        }
+    }
+
+    /// Number of addresses in the set, which is 2^64 when all 64 bits float.
+    pub fn combinations(&self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        address & self.ones == self.ones && address & self.zeros == 0
+    }
+
+    /// Every address in the set, by counting through the subsets of the
+    /// floating bits.
+    pub fn addresses(&self) -> impl Iterator<Item = u64> {
+        let Mask { ones, floating, .. } = *self;
+        let mut subset = Some(0u64);
+
+        std::iter::from_fn(move || {
+            let current = subset?;
+            let next = current.wrapping_sub(floating) & floating;
+            subset = if next == 0 { None } else { Some(next) };
+
+            Some(ones | current)
+        })
+    }
+
+    /// Whether both address sets have an address in common.
+    pub fn overlaps(&self, other: &Mask) -> bool {
+        self.ones & other.zeros == 0 && self.zeros & other.ones == 0
+    }
+
+    /// The addresses in `self` that aren't in `other`, as disjoint sets.
+    /// Every bit that is floating here but fixed in `other` splits off the
+    /// half that disagrees with `other`.
+    pub fn subtract(&self, other: &Mask) -> Vec<Mask> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+
+        let mut rest = *self;
+        let mut pieces = vec![];
+        let mut split = self.floating & other.fixed();
+
+        while split != 0 {
+            let bit = split & split.wrapping_neg();
+            let floating = rest.floating & !bit;
+
+            if other.ones & bit != 0 {
+                pieces.push(Mask {
+                    zeros: rest.zeros | bit,
+                    floating,
+                    ..rest
+                });
+                rest.ones |= bit;
+            } else {
+                pieces.push(Mask {
+                    ones: rest.ones | bit,
+                    floating,
+                    ..rest
+                });
+                rest.zeros |= bit;
+            }
+            rest.floating = floating;
+            split &= !bit;
+        }
+
+        pieces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(s: &str) -> Mask {
+        format!("{:0>36}", s).parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let parsed = mask("X1001X");
+
+        assert_eq!(parsed.ones, 0b010010);
+        assert_eq!(parsed.floating, 0b100001);
+        assert_eq!(parsed.zeros, (1 << 36) - 1 - 0b110011);
+        assert_eq!(parsed.to_string(), format!("{:0>36}", "X1001X"));
+        assert_eq!(
+            Mask::parse("X1001X", 6),
+            Ok(Mask {
+                zeros: 0b001100,
+                ..parsed
+            })
+        );
+        assert_eq!(parsed.format(8), "00X1001X");
+
+        assert_eq!("X1001X".parse::<Mask>(), Err(MaskError::Length(6, 36)));
+        assert_eq!(
+            format!("{:0>36}", "X1201X").parse::<Mask>(),
+            Err(MaskError::Char('2', 32))
+        );
+    }
+
+    #[test]
+    fn apply_and_decode() {
+        let value_mask = mask("1XXXX0X");
+        assert_eq!(value_mask.apply(11), 73);
+        assert_eq!(value_mask.apply(0), 64);
+
+        let addresses = mask("X1001X").decode(42);
+        assert_eq!(addresses, mask("X1101X"));
+        assert_eq!(addresses.combinations(), 4);
+        assert_eq!(
+            Mask::parse(&"X".repeat(64), 64).unwrap().combinations(),
+            1 << 64
+        );
+        assert_eq!(
+            addresses.addresses().collect::<Vec<_>>(),
+            vec![26, 27, 58, 59]
+        );
+        assert!(addresses.contains(59));
+        assert!(!addresses.contains(42));
+    }
+
+    #[test]
+    fn overlap() {
+        let pieces = mask("X11XXX").subtract(&mask("XXX011"));
+
+        assert_eq!(pieces, vec![mask("X11XX0"), mask("X11X01"), mask("X11111")]);
+    }
+
+    #[test]
+    fn fully_covered() {
+        assert_eq!(mask("X11XXX").subtract(&mask("X11XXX")), vec![]);
+    }
+
+    #[test]
+    fn no_overlap() {
+        assert!(!mask("X111XX").overlaps(&mask("X110XX")));
+        assert_eq!(
+            mask("X111XX").subtract(&mask("X110XX")),
+            vec![mask("X111XX")]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::mask::Mask;
+use shared::prelude::*;
+
+/// Memory written through floating addresses. Each write covers a set of
+/// addresses described by a `Mask`, and the stored sets are kept disjoint by
+/// cutting the overlap with every new write out of the older ones. That keeps
+/// the number of stored sets small even when a single write covers billions
+/// of addresses.
+///
+/// Writes to a single address, like every write of a version 1 program, are
+/// kept in a plain map instead, and are never part of a stored set.
+#[derive(Debug, Default, Clone)]
+pub struct FloatingMemory {
+    cells: Vec<(Mask, u64)>,
+    single: HashMap<u64, u64>,
+}
+
+impl FloatingMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes `value` to every address in `addresses`.
+    pub fn write(&mut self, addresses: Mask, value: u64) {
+        if addresses.floating != 0 {
+            self.single
+                .retain(|&address, _| !addresses.contains(address));
+        } else if self.cells.is_empty() {
+            self.single.insert(addresses.ones, value);
+            return;
+        }
+
+        self.cells = self
+            .cells
+            .iter()
+            .flat_map(|(cell, value)| {
+                cell.subtract(&addresses)
+                    .into_iter()
+                    .map(move |cell| (cell, *value))
+            })
+            .collect();
+        if addresses.floating == 0 {
+            self.single.insert(addresses.ones, value);
+        } else {
+            self.cells.push((addresses, value));
+        }
+    }
+
+    /// The value at `address`, 0 if it was never written.
+    pub fn read(&self, address: u64) -> u64 {
+        if let Some(&value) = self.single.get(&address) {
+            return value;
+        }
+
+        self.cells
+            .iter()
+            .find(|(cell, _)| cell.contains(address))
+            .map_or(0, |(_, value)| *value)
+    }
+
+    /// Every written address with its value, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.cells
+            .iter()
+            .flat_map(|(cell, value)| cell.addresses().map(move |address| (address, *value)))
+            .chain(
+                self.single
+                    .iter()
+                    .map(|(&address, &value)| (address, value)),
+            )
+    }
+
+    /// Number of distinct addresses written to.
+    pub fn len(&self) -> u128 {
+        self.single.len() as u128
+            + self
+                .cells
+                .iter()
+                .map(|(cell, _)| cell.combinations())
+                .sum::<u128>()
+    }
+
+    /// The sum of all values in memory, without visiting every address.
+    /// `None` if it doesn't fit in 128 bits.
+    pub fn sum(&self) -> Option<u128> {
+        let single = self.single.values().map(|&value| Some(value as u128));
+        let cells = self
+            .cells
+            .iter()
+            .map(|(cell, value)| (*value as u128).checked_mul(cell.combinations()));
+
+        single
+            .chain(cells)
+            .try_fold(0u128, |sum, value| sum.checked_add(value?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(s: &str) -> Mask {
+        format!("{:0>36}", s).parse().unwrap()
+    }
+
+    #[test]
+    fn writes_and_reads() {
+        let mut memory = FloatingMemory::new();
+        assert_eq!(memory.len(), 0);
+
+        memory.write(mask("X1001X").decode(42), 100);
+        memory.write(mask("X0XX").decode(26), 1);
+
+        assert_eq!(memory.read(58), 100);
+        assert_eq!(memory.read(59), 100);
+        assert_eq!(memory.read(26), 1);
+        assert_eq!(memory.read(16), 1);
+        assert_eq!(memory.read(42), 0);
+        assert_eq!(memory.len(), 10);
+        assert_eq!(memory.sum(), Some(208));
+
+        let mut pairs: Vec<(u64, u64)> = memory.iter().collect();
+        pairs.sort_unstable();
+        assert_eq!(
+            pairs,
+            vec![
+                (16, 1),
+                (17, 1),
+                (18, 1),
+                (19, 1),
+                (24, 1),
+                (25, 1),
+                (26, 1),
+                (27, 1),
+                (58, 100),
+                (59, 100)
+            ]
+        );
+        assert_eq!(
+            memory.sum(),
+            Some(memory.iter().map(|(_, v)| v as u128).sum())
+        );
+    }
+
+    #[test]
+    fn single_addresses() {
+        let mut memory = FloatingMemory::new();
+
+        memory.write(mask("101"), 7);
+        memory.write(mask("X0X").decode(0), 1);
+        assert_eq!(memory.read(5), 1);
+        assert_eq!(memory.len(), 4);
+
+        memory.write(mask("100"), 9);
+        memory.write(mask("110"), 3);
+        assert_eq!(memory.read(4), 9);
+        assert_eq!(memory.read(5), 1);
+        assert_eq!(memory.len(), 5);
+        assert_eq!(memory.sum(), Some(1 + 9 + 1 + 1 + 3));
+    }
+
+    #[test]
+    fn wide_words() {
+        let everything = Mask::parse(&"X".repeat(64), 64).unwrap();
+        let mut memory = FloatingMemory::new();
+
+        memory.write(everything, u64::MAX);
+        assert_eq!(memory.len(), 1 << 64);
+        assert_eq!(memory.sum(), Some(u64::MAX as u128 * (1 << 64)));
+
+        memory.write(everything, 2);
+        memory.write(Mask::address(3, 64), u64::MAX);
+        assert_eq!(memory.len(), 1 << 64);
+        assert_eq!(memory.sum(), Some((1 << 65) - 2 + u64::MAX as u128));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::Game;
+use shared::prelude::*;
+use std::io::{self, Write};
+
+/// Where a number shows up in a played game, in 1-based turns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Occurrences {
+    pub first: usize,
+    pub last: usize,
+    pub count: usize,
+}
+
+/// The longest a number went unspoken before being repeated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gap {
+    pub number: u64,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl Gap {
+    pub fn len(&self) -> usize {
+        self.to - self.from
+    }
+}
+
+/// Statistics over the first `turns` numbers of a game.
+pub struct Analysis {
+    pub turns: usize,
+    occurrences: HashMap<u64, Occurrences>,
+    largest_gap: Option<Gap>,
+}
+
+impl Analysis {
+    pub fn new(initial: &[u64], turns: usize) -> Self {
+        let mut occurrences: HashMap<u64, Occurrences> = HashMap::new();
+        let mut largest_gap: Option<Gap> = None;
+
+        for (idx, number) in Game::with_turns(initial, turns).take(turns).enumerate() {
+            let turn = idx + 1;
+
+            match occurrences.get_mut(&number) {
+                Some(seen) => {
+                    if largest_gap.map_or(true, |gap| turn - seen.last > gap.len()) {
+                        largest_gap = Some(Gap {
+                            number,
+                            from: seen.last,
+                            to: turn,
+                        });
+                    }
+                    seen.last = turn;
+                    seen.count += 1;
+                }
+                None => {
+                    occurrences.insert(
+                        number,
+                        Occurrences {
+                            first: turn,
+                            last: turn,
+                            count: 1,
+                        },
+                    );
+                }
+            }
+        }
+
+        Analysis {
+            turns,
+            occurrences,
+            largest_gap,
+        }
+    }
+
+    pub fn occurrences(&self, number: u64) -> Option<Occurrences> {
+        self.occurrences.get(&number).copied()
+    }
+
+    pub fn first_occurrence(&self, number: u64) -> Option<usize> {
+        self.occurrences(number).map(|o| o.first)
+    }
+
+    pub fn last_occurrence(&self, number: u64) -> Option<usize> {
+        self.occurrences(number).map(|o| o.last)
+    }
+
+    /// Number of distinct numbers spoken.
+    pub fn distinct(&self) -> usize {
+        self.occurrences.len()
+    }
+
+    /// How often each number was spoken, ordered by number.
+    pub fn histogram(&self) -> Vec<(u64, usize)> {
+        self.occurrences
+            .iter()
+            .map(|(&number, o)| (number, o.count))
+            .sorted()
+            .collect()
+    }
+
+    /// The largest distance between two turns speaking the same number. Ties
+    /// go to the earliest one.
+    pub fn largest_gap(&self) -> Option<Gap> {
+        self.largest_gap
+    }
+}
+
+/// Writes the first `turns` numbers of a game as `turn,number` rows.
+pub fn write_csv(initial: &[u64], turns: usize, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "turn,number")?;
+    for (idx, number) in Game::with_turns(initial, turns).take(turns).enumerate() {
+        writeln!(out, "{},{}", idx + 1, number)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics() {
+        // 0 3 6 0 3 3 1 0 4 0
+        let analysis = Analysis::new(&[0, 3, 6], 10);
+
+        assert_eq!(
+            analysis.occurrences(0),
+            Some(Occurrences {
+                first: 1,
+                last: 10,
+                count: 4
+            })
+        );
+        assert_eq!(analysis.first_occurrence(3), Some(2));
+        assert_eq!(analysis.last_occurrence(3), Some(6));
+        assert_eq!(analysis.first_occurrence(2), None);
+        assert_eq!(analysis.distinct(), 5);
+        assert_eq!(
+            analysis.histogram(),
+            vec![(0, 4), (1, 1), (3, 3), (4, 1), (6, 1)]
+        );
+        assert_eq!(
+            analysis.largest_gap(),
+            Some(Gap {
+                number: 0,
+                from: 4,
+                to: 8
+            })
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let mut out = vec![];
+        write_csv(&[0, 3, 6], 5, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "turn,number\n1,0\n2,3\n3,6\n4,0\n5,3\n"
+        );
+    }
+}
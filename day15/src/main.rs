@@ -1,29 +1,109 @@
+mod analysis;
+
+use analysis::Analysis;
 #[allow(unused_imports)]
 use shared::prelude::*;
+use std::num::ParseIntError;
 
-const INPUT: &'static str = "12,20,0,6,1,17,7";
+const INPUT: &str = include_str!("./input.txt");
 
 type Data = Vec<u64>;
 type Solution = u64;
 
-fn parse_input(input: &str) -> Data {
-    input.split(",").filter_map(|n| n.parse().ok()).collect()
+fn parse_input(input: &str) -> Result<Data, ParseIntError> {
+    input.trim().split(',').map(|n| n.trim().parse()).collect()
+}
+
+/// When each number was last spoken, as a 0-based turn.
+enum Memory {
+    Sparse(HashMap<u64, usize>),
+    /// `turns` is indexed by number and holds the turn + 1, so 0 can mean
+    /// "never spoken". Every number after the starting ones is smaller than
+    /// the turn it's spoken on, so when the number of turns is known up front
+    /// only starting numbers that are too large end up in `overflow`.
+    Dense {
+        turns: Vec<u32>,
+        overflow: HashMap<u64, usize>,
+    },
+}
+
+impl Memory {
+    fn dense(size: usize) -> Self {
+        Memory::Dense {
+            turns: vec![0; size],
+            overflow: HashMap::new(),
+        }
+    }
+
+    fn get(&self, number: u64) -> Option<usize> {
+        match self {
+            Memory::Sparse(mem) => mem.get(&number).copied(),
+            Memory::Dense { turns, overflow } => match turns.get(number as usize) {
+                Some(&0) => None,
+                Some(&turn) => Some(turn as usize - 1),
+                None => overflow.get(&number).copied(),
+            },
+        }
+    }
+
+    fn insert(&mut self, number: u64, turn: usize) {
+        match self {
+            Memory::Sparse(mem) => {
+                mem.insert(number, turn);
+            }
+            // The turn no longer fits in the array, so move everything to a
+            // map.
+            Memory::Dense { turns, overflow } if turn >= u32::MAX as usize => {
+                let mut mem = std::mem::take(overflow);
+                mem.extend(
+                    turns
+                        .iter()
+                        .enumerate()
+                        .filter(|&(_, &turn)| turn > 0)
+                        .map(|(number, &turn)| (number as u64, turn as usize - 1)),
+                );
+                mem.insert(number, turn);
+                *self = Memory::Sparse(mem);
+            }
+            Memory::Dense { turns, overflow } => match turns.get_mut(number as usize) {
+                Some(slot) => *slot = (turn + 1) as u32,
+                None => {
+                    overflow.insert(number, turn);
+                }
+            },
+        }
+    }
 }
 
 struct Game {
-    mem: HashMap<u64, usize>,
+    mem: Memory,
     idx: usize,
     initial: Vec<u64>,
     last: u64,
 }
 
 impl Game {
-    fn new(initial: &Vec<u64>) -> Self {
+    fn new(initial: &[u64]) -> Self {
         Self {
             idx: 0,
             last: 0,
-            mem: HashMap::new(),
-            initial: initial.clone(),
+            mem: Memory::Sparse(HashMap::new()),
+            initial: initial.to_vec(),
+        }
+    }
+
+    /// A game that is expected to run for `turns` turns, which lets it keep
+    /// its memory in a flat array of `turns` entries instead of a hash map.
+    /// It can still be played beyond that: larger numbers go in a map next
+    /// to the array, and past `u32::MAX` turns everything moves to a map.
+    fn with_turns(initial: &[u64], turns: usize) -> Self {
+        if turns > u32::MAX as usize {
+            return Self::new(initial);
+        }
+
+        Self {
+            mem: Memory::dense(turns),
+            ..Self::new(initial)
         }
     }
 }
@@ -36,8 +116,8 @@ impl Iterator for Game {
             self.initial[self.idx]
         } else {
             self.mem
-                .get(&self.last)
-                .map(|n| (self.idx - 1 - *n) as u64)
+                .get(self.last)
+                .map(|n| (self.idx - 1 - n) as u64)
                 .unwrap_or(0)
         };
 
@@ -51,31 +131,144 @@ impl Iterator for Game {
     }
 }
 
-fn solve_a(data: &Data) -> Solution {
-    Game::new(&data).nth(2020 - 1).unwrap()
+/// The number spoken on turn `turns` (1-based).
+fn play(data: &[u64], turns: usize) -> Solution {
+    Game::with_turns(data, turns).nth(turns - 1).unwrap()
+}
+
+fn solve_a(data: &[u64]) -> Solution {
+    play(data, 2020)
 }
 
-fn solve_b(data: &Data) -> Solution {
-    Game::new(&data).nth(30000000 - 1).unwrap()
+fn solve_b(data: &[u64]) -> Solution {
+    play(data, 30_000_000)
+}
+
+const USAGE: &str = "\
+usage: day15 [--start NUMBERS] (--stats TURNS [NUMBER...] | --csv TURNS)
+
+--stats summarises the first TURNS numbers and where the given numbers
+occur, --csv prints them as turn,number rows. --start replaces the
+starting numbers from the input, e.g. --start 0,3,6.";
+
+fn print_stats(data: &[u64], turns: usize, numbers: &[u64]) {
+    let analysis = Analysis::new(data, turns);
+
+    println!("turns: {}", analysis.turns);
+    println!("distinct numbers: {}", analysis.distinct());
+    if let Some(gap) = analysis.largest_gap() {
+        println!(
+            "largest gap: {} turns, {} on turns {} and {}",
+            gap.len(),
+            gap.number,
+            gap.from,
+            gap.to
+        );
+    }
+    if let Some((number, count)) = analysis.histogram().into_iter().max_by_key(|&(_, c)| c) {
+        println!("most frequent: {} ({} times)", number, count);
+    }
+
+    for &number in numbers {
+        match (
+            analysis.first_occurrence(number),
+            analysis.last_occurrence(number),
+        ) {
+            (Some(first), Some(last)) => {
+                println!("{}: first on turn {}, last on turn {}", number, first, last)
+            }
+            _ => println!("{}: never spoken", number),
+        }
+    }
 }
 
 fn main() {
-    let data = parse_input(INPUT);
+    let mut data = parse_input(INPUT).unwrap_or_else(|error| {
+        eprintln!("invalid input: {}", error);
+        std::process::exit(1);
+    });
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() {
+        println!("Part A: {}", solve_a(&data));
+        println!("Part B: {}", solve_b(&data));
+        return;
+    }
+
+    let mut args = &args[..];
+    if let [flag, start, rest @ ..] = args {
+        if flag == "--start" {
+            data = parse_input(start).or_usage(USAGE);
+            args = rest;
+        }
+    }
 
-    println!("Part A: {}", solve_a(&data));
-    println!("Part B: {}", solve_b(&data));
+    match args {
+        [flag, turns, numbers @ ..] if flag == "--stats" => {
+            let numbers: Vec<u64> = numbers.iter().map(|n| n.parse().or_usage(USAGE)).collect();
+            print_stats(&data, turns.parse().or_usage(USAGE), &numbers);
+        }
+        [flag, turns] if flag == "--csv" => {
+            let turns = turns.parse().or_usage(USAGE);
+            analysis::write_csv(&data, turns, &mut std::io::stdout().lock()).unwrap()
+        }
+        _ => usage_error(USAGE),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE: &'static str = "0,3,6";
+    const EXAMPLE: &str = "0,3,6";
 
     #[test]
     fn examples_a() {
-        let data = parse_input(EXAMPLE);
+        let data = parse_input(EXAMPLE).unwrap();
 
         assert_eq!(solve_a(&data), 436);
+        assert_eq!(solve_a(&parse_input("3,1,2").unwrap()), 1836);
+        assert!(parse_input("0,x").is_err());
+    }
+
+    #[test]
+    fn examples_b() {
+        let data = parse_input(EXAMPLE).unwrap();
+
+        assert_eq!(solve_b(&data), 175594);
+    }
+
+    #[test]
+    fn dense_matches_sparse() {
+        let data = parse_input("2,1000,3").unwrap();
+
+        // Stopping the dense game short puts most numbers past its array.
+        let sparse: Vec<u64> = Game::new(&data).take(5000).collect();
+        let dense: Vec<u64> = Game::with_turns(&data, 10).take(5000).collect();
+
+        assert_eq!(sparse, dense);
+    }
+
+    #[test]
+    fn large_starting_numbers() {
+        let data = [4_000_000_000_000, 1];
+
+        assert_eq!(play(&data, 10), 0);
+        assert_eq!(
+            Game::with_turns(&data, 10).take(10).collect::<Vec<_>>(),
+            Game::new(&data).take(10).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn turns_past_u32() {
+        let mut mem = Memory::dense(4);
+        mem.insert(2, 3);
+        mem.insert(1, u32::MAX as usize);
+
+        assert!(matches!(mem, Memory::Sparse(_)));
+        assert_eq!(mem.get(2), Some(3));
+        assert_eq!(mem.get(1), Some(u32::MAX as usize));
+        assert_eq!(mem.get(0), None);
     }
 }
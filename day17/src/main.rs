@@ -1,16 +1,21 @@
 #![feature(min_const_generics)]
-use std::convert::TryInto;
+mod report;
+mod rules;
+mod slices;
 
+use rules::{Neighbourhood, Rule};
 #[allow(unused_imports)]
 use shared::prelude::*;
+use slices::SliceError;
+use std::io;
 
-const INPUT: &'static str = include_str!("./input.txt");
+const INPUT: &str = include_str!("./input.txt");
 
 type Data = Vec<Coord<2>>;
 type Solution = usize;
 type Coord<const DIMENSIONS: usize> = [isize; DIMENSIONS];
 
-fn parse_input(input: &str) -> Vec<Coord<2>> {
+fn parse_input(input: &str) -> Data {
     input
         .lines()
         .enumerate()
@@ -23,9 +28,17 @@ fn parse_input(input: &str) -> Vec<Coord<2>> {
         .collect()
 }
 
+/// A pocket dimension. The puzzle starts from a flat slice, so the state
+/// stays mirror symmetric in every dimension after the first two. While it
+/// is `mirrored`, only the cells with no negative coordinates in those
+/// dimensions are stored, each standing in for all of its mirror images.
 #[derive(Debug, Clone)]
-struct Conway<const DIMENSIONS: usize> {
+pub struct Conway<const DIMENSIONS: usize> {
     grid: HashSet<Coord<DIMENSIONS>>,
+    mirrored: bool,
+    rule: Rule,
+    neighbourhood: Neighbourhood,
+    offsets: Vec<Coord<DIMENSIONS>>,
 }
 
 impl<const DIMENSIONS: usize> Conway<DIMENSIONS> {
@@ -35,61 +48,122 @@ impl<const DIMENSIONS: usize> Conway<DIMENSIONS> {
     {
         let grid: HashSet<_> = coords
             .into_iter()
-            .map(|coord| {
-                [coord.to_vec(), vec![0; DIMENSIONS - 2]]
-                    .concat()
-                    .try_into()
-                    .unwrap()
+            .map(|[x, y]| {
+                let mut coord = [0; DIMENSIONS];
+                coord[0] = x;
+                coord[1] = y;
+                coord
             })
             .collect();
 
-        Self { grid }
+        Self {
+            grid,
+            mirrored: true,
+            rule: Rule::default(),
+            neighbourhood: Neighbourhood::Moore,
+            offsets: Self::offsets(Neighbourhood::Moore),
+        }
     }
 
-    fn iter_neighbours(&self, coord: Coord<DIMENSIONS>) -> impl Iterator<Item = Coord<DIMENSIONS>> {
-        (0isize..(3isize.pow(DIMENSIONS as u32)))
-            .filter(|n| (3isize.pow(DIMENSIONS as u32)) / 2 != *n)
-            .map(move |offset| {
-                (0isize..(DIMENSIONS as isize))
-                    .map(|idx| {
-                        let offset = (offset / 3isize.pow(idx as u32)) % 3;
-                        coord[idx as usize] - 1 + offset
-                    })
-                    .collect::<Vec<_>>()
-                    .try_into()
-                    .unwrap()
+    /// Starts from any set of active cells. Symmetric states are folded,
+    /// others are simulated in full.
+    fn from_cells(cells: HashSet<Coord<DIMENSIONS>>) -> Self {
+        let mirrored = cells.iter().all(|cell| {
+            (2..DIMENSIONS).all(|idx| {
+                let mut image = *cell;
+                image[idx] = -image[idx];
+                cells.contains(&image)
             })
+        });
+        let grid = if mirrored {
+            cells
+                .into_iter()
+                .filter(|cell| cell[2..].iter().all(|&n| n >= 0))
+                .collect()
+        } else {
+            cells
+        };
+
+        Self {
+            grid,
+            mirrored,
+            ..Self::from_2d(vec![])
+        }
     }
 
-    fn next_state(&mut self) {
-        let existing: HashSet<_> = self
-            .grid
-            .iter()
-            .filter(|coord| {
-                (2..=3).contains(
-                    &self
-                        .iter_neighbours(**coord)
-                        .filter(|neighbour| self.grid.contains(neighbour))
-                        .count(),
-                )
-            })
-            .map(|coord| *coord)
-            .collect();
+    /// Parses a state printed like the puzzle does, see `slices::parse`.
+    fn parse(input: &str) -> Result<Self, SliceError> {
+        slices::parse(input).map(Self::from_cells)
+    }
 
-        let new_active: HashSet<_> = self
-            .grid
-            .iter()
-            .flat_map(|coord| self.iter_neighbours(*coord))
-            .filter(|coord| !self.grid.contains(coord))
-            .filter(|coord| {
-                self.iter_neighbours(*coord)
-                    .filter(|neighbour| self.grid.contains(neighbour))
-                    .count()
-                    == 3
+    fn with_rule(self, rule: Rule) -> Self {
+        Self { rule, ..self }
+    }
+
+    fn with_neighbourhood(self, neighbourhood: Neighbourhood) -> Self {
+        Self {
+            neighbourhood,
+            offsets: Self::offsets(neighbourhood),
+            ..self
+        }
+    }
+
+    fn offsets(neighbourhood: Neighbourhood) -> Vec<Coord<DIMENSIONS>> {
+        let count = 3usize.pow(DIMENSIONS as u32);
+
+        (0..count)
+            .filter(|&n| n != count / 2)
+            .map(|mut n| {
+                let mut offset = [0; DIMENSIONS];
+                for delta in offset.iter_mut() {
+                    *delta = (n % 3) as isize - 1;
+                    n /= 3;
+                }
+                offset
             })
-            .collect();
+            .filter(|offset| neighbourhood.contains(offset))
+            .collect()
+    }
 
-        self.grid = &existing | &new_active;
+    /// For every active stored cell and every stored cell next to one, the
+    /// number of active neighbours it has in the full, unfolded space.
+    fn neighbour_counts(&self) -> HashMap<Coord<DIMENSIONS>, usize> {
+        let mut counts = HashMap::new();
+
+        for coord in &self.grid {
+            counts.entry(*coord).or_insert(0);
+            'offsets: for offset in &self.offsets {
+                let mut neighbour = *coord;
+                let mut weight = 1;
+
+                for idx in 0..DIMENSIONS {
+                    neighbour[idx] += offset[idx];
+                    if idx < 2 || !self.mirrored {
+                        continue;
+                    }
+                    match (coord[idx], neighbour[idx]) {
+                        (_, n) if n < 0 => continue 'offsets,
+                        // The mirror image of `coord` at -1 is a neighbour
+                        // as well.
+                        (1, 0) => weight *= 2,
+                        _ => {}
+                    }
+                }
+
+                *counts.entry(neighbour).or_insert(0) += weight;
+            }
+        }
+
+        counts
+    }
+
+    fn next_state(&mut self) {
+        self.grid = self
+            .neighbour_counts()
+            .into_iter()
+            .filter(|(coord, count)| self.rule.next(self.grid.contains(coord), *count))
+            .map(|(coord, _)| coord)
+            .collect();
     }
 
     fn run(&mut self, number_of_cycles: usize) {
@@ -99,36 +173,230 @@ impl<const DIMENSIONS: usize> Conway<DIMENSIONS> {
     }
 
     fn number_of_active(&self) -> usize {
-        self.grid.len()
+        if !self.mirrored {
+            return self.grid.len();
+        }
+
+        self.grid
+            .iter()
+            .map(|coord| 1 << coord[2..].iter().filter(|&&n| n != 0).count())
+            .sum()
+    }
+
+    /// Every active cell with its mirror images unfolded, in order.
+    fn active_cells(&self) -> Vec<Coord<DIMENSIONS>> {
+        self.grid
+            .iter()
+            .flat_map(|&coord| {
+                (2..DIMENSIONS)
+                    .filter(|&idx| self.mirrored && coord[idx] != 0)
+                    .fold(vec![coord], |images, idx| {
+                        images
+                            .into_iter()
+                            .flat_map(|image| {
+                                let mut mirrored = image;
+                                mirrored[idx] = -mirrored[idx];
+                                vec![image, mirrored]
+                            })
+                            .collect()
+                    })
+            })
+            .sorted()
+            .collect()
+    }
+
+    /// The smallest and largest coordinate of the active cells along each
+    /// axis.
+    fn bounding_box(&self) -> Option<(Coord<DIMENSIONS>, Coord<DIMENSIONS>)> {
+        let mut cells = self.grid.iter();
+        let first = *cells.next()?;
+
+        let (mut min, max) = cells.fold((first, first), |(mut min, mut max), coord| {
+            for idx in 0..DIMENSIONS {
+                min[idx] = min[idx].min(coord[idx]);
+                max[idx] = max[idx].max(coord[idx]);
+            }
+            (min, max)
+        });
+        if self.mirrored {
+            for idx in 2..DIMENSIONS {
+                min[idx] = -max[idx];
+            }
+        }
+
+        Some((min, max))
     }
 }
 
-fn solve_a(data: &Data) -> Solution {
-    let mut conway: Conway<3> = Conway::from_2d(data.clone());
+fn simulate<const DIMENSIONS: usize>(conway: Conway<DIMENSIONS>, options: &Options) -> Solution {
+    let mut conway = conway
+        .with_rule(options.rule.clone())
+        .with_neighbourhood(options.neighbourhood);
+
+    if !(options.show || options.bounds || options.csv) {
+        conway.run(options.cycles);
+        return conway.number_of_active();
+    }
+
+    if options.csv {
+        report::write_csv_header(DIMENSIONS, &mut io::stdout()).unwrap();
+    }
+    report_cycle(0, &conway, options);
+    for cycle in 1..=options.cycles {
+        conway.next_state();
+        report_cycle(cycle, &conway, options);
+    }
 
-    conway.run(6);
     conway.number_of_active()
 }
 
-fn solve_b(data: &Data) -> Solution {
-    let mut conway: Conway<4> = Conway::from_2d(data.clone());
+fn report_cycle<const DIMENSIONS: usize>(
+    cycle: usize,
+    conway: &Conway<DIMENSIONS>,
+    options: &Options,
+) {
+    if options.show {
+        match cycle {
+            0 => println!("Before any cycles:\n"),
+            1 => println!("After 1 cycle:\n"),
+            _ => println!("After {} cycles:\n", cycle),
+        }
+        println!("{}\n\n", report::render(conway));
+    }
+    if options.bounds {
+        let bounds = conway
+            .bounding_box()
+            .map_or("empty".to_string(), report::format_bounds);
+        println!(
+            "cycle {}: {} active, {}",
+            cycle,
+            conway.number_of_active(),
+            bounds
+        );
+    }
+    if options.csv {
+        report::write_csv(cycle, conway, &mut io::stdout()).unwrap();
+    }
+}
 
-    conway.run(6);
-    conway.number_of_active()
+fn solve<const DIMENSIONS: usize>(data: &[Coord<2>]) -> Solution {
+    simulate::<DIMENSIONS>(Conway::from_2d(data.to_vec()), &Options::default())
+}
+
+fn solve_a(data: &[Coord<2>]) -> Solution {
+    solve::<3>(data)
+}
+
+fn solve_b(data: &[Coord<2>]) -> Solution {
+    solve::<4>(data)
+}
+
+struct Options {
+    dimensions: usize,
+    cycles: usize,
+    rule: Rule,
+    neighbourhood: Neighbourhood,
+    show: bool,
+    bounds: bool,
+    csv: bool,
+    input: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            dimensions: 3,
+            cycles: 6,
+            rule: Rule::default(),
+            neighbourhood: Neighbourhood::Moore,
+            show: false,
+            bounds: false,
+            csv: false,
+            input: None,
+        }
+    }
+}
+
+const USAGE: &str = "\
+usage: day17 [--dimensions 2-6] [--cycles N] [--rule B3/S23] [--von-neumann]
+             [--show | --bounds | --csv] [--input FILE]
+
+Runs the input with another rule, neighbourhood or number of dimensions and
+prints the number of active cubes. Every cycle, --show prints the slices like
+the puzzle does, --bounds prints the bounding box of the active cubes and
+--csv prints their coordinates. With --csv nothing else is printed.
+
+--input starts from another state instead, either a single slice or slices
+printed like the puzzle (and --show) does.";
+
+fn parse_options(args: &[String]) -> Options {
+    let mut options = Options::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().or_usage(USAGE);
+        match arg.as_str() {
+            "--dimensions" => options.dimensions = value().parse().or_usage(USAGE),
+            "--cycles" => options.cycles = value().parse().or_usage(USAGE),
+            "--rule" => options.rule = value().parse().or_usage(USAGE),
+            "--von-neumann" => options.neighbourhood = Neighbourhood::VonNeumann,
+            "--show" => options.show = true,
+            "--bounds" => options.bounds = true,
+            "--csv" => options.csv = true,
+            "--input" => options.input = Some(value().clone()),
+            _ => usage_error(USAGE),
+        }
+    }
+    if options.csv && (options.show || options.bounds) {
+        eprintln!("--csv can't be combined with --show or --bounds");
+        usage_error(USAGE);
+    }
+
+    options
 }
 
 fn main() {
     let data = parse_input(INPUT);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() {
+        println!("Part A: {}", solve_a(&data));
+        println!("Part B: {}", solve_b(&data));
+        return;
+    }
+
+    let options = parse_options(&args);
+    let input = match &options.input {
+        Some(path) => std::fs::read_to_string(path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(1);
+        }),
+        None => INPUT.to_string(),
+    };
+    let active = match options.dimensions {
+        2 => Conway::<2>::parse(&input).map(|conway| simulate(conway, &options)),
+        3 => Conway::<3>::parse(&input).map(|conway| simulate(conway, &options)),
+        4 => Conway::<4>::parse(&input).map(|conway| simulate(conway, &options)),
+        5 => Conway::<5>::parse(&input).map(|conway| simulate(conway, &options)),
+        6 => Conway::<6>::parse(&input).map(|conway| simulate(conway, &options)),
+        _ => usage_error(USAGE),
+    };
 
-    println!("Part A: {}", solve_a(&data));
-    println!("Part B: {}", solve_b(&data));
+    match active {
+        Ok(_) if options.csv => {}
+        Ok(active) => println!("{}", active),
+        Err(error) => {
+            eprintln!("invalid input: {}", error);
+            std::process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE: &'static str = ".#.
+    const EXAMPLE: &str = ".#.
 ..#
 ###";
 
@@ -145,4 +413,59 @@ mod tests {
 
         assert_eq!(solve_b(&data), 848);
     }
+
+    #[test]
+    fn more_dimensions() {
+        let data = parse_input(EXAMPLE);
+
+        assert_eq!(solve::<5>(&data), 5760);
+    }
+
+    #[test]
+    fn resume() {
+        let mut conway: Conway<4> = Conway::from_2d(parse_input(EXAMPLE));
+        conway.run(2);
+
+        let mut resumed: Conway<4> = Conway::parse(&report::render(&conway)).unwrap();
+        assert!(resumed.mirrored);
+        assert_eq!(resumed.number_of_active(), conway.number_of_active());
+        resumed.run(4);
+        assert_eq!(resumed.number_of_active(), 848);
+
+        // Away from z=0 the state isn't symmetric and can't be folded.
+        let mut shifted: Conway<3> = Conway::parse(&format!("z=5\n{}", EXAMPLE)).unwrap();
+        assert!(!shifted.mirrored);
+        shifted.run(6);
+        assert_eq!(shifted.number_of_active(), 112);
+    }
+
+    #[test]
+    fn other_rules() {
+        let blinker = vec![[0, 0], [1, 0], [2, 0]];
+        let mut life: Conway<2> = Conway::from_2d(blinker);
+
+        life.run(1);
+        assert_eq!(
+            life.grid.iter().copied().sorted().collect::<Vec<_>>(),
+            vec![[1, -1], [1, 0], [1, 1]]
+        );
+
+        // Under B1/S with a von Neumann neighbourhood a lone cell is replaced
+        // by the six cells sharing a face with it.
+        let mut diamond: Conway<3> = Conway::from_2d(vec![[0, 0]])
+            .with_rule("B1/S".parse().unwrap())
+            .with_neighbourhood(Neighbourhood::VonNeumann);
+        diamond.run(1);
+        assert_eq!(diamond.number_of_active(), 6);
+
+        // The empty centre has six neighbours, which only HighLife counts as
+        // a birth.
+        let ring = parse_input("###\n...\n###");
+        let mut life: Conway<2> = Conway::from_2d(ring.clone());
+        let mut highlife: Conway<2> = Conway::from_2d(ring).with_rule("B36/S23".parse().unwrap());
+        life.run(1);
+        highlife.run(1);
+        assert!(!life.grid.contains(&[1, 1]));
+        assert!(highlife.grid.contains(&[1, 1]));
+    }
 }
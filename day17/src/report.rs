@@ -0,0 +1,131 @@
+use crate::{Conway, Coord};
+use std::io::{self, Write};
+
+pub const AXES: [&str; 6] = ["x", "y", "z", "w", "v", "u"];
+
+/// The active cells as 2D slices of the bounding box, like the puzzle
+/// prints them: one block per combination of the other coordinates,
+/// headed by `z=…, w=…`, with the last axis changing slowest.
+pub fn render<const DIMENSIONS: usize>(conway: &Conway<DIMENSIONS>) -> String {
+    let (min, max) = match conway.bounding_box() {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+    let active = conway.active_cells();
+
+    let mut slices = vec![];
+    let mut layer = min;
+    loop {
+        let header = (2..DIMENSIONS)
+            .map(|idx| format!("{}={}", AXES[idx], layer[idx]))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rows = (min[1]..=max[1]).map(|y| {
+            (min[0]..=max[0])
+                .map(|x| {
+                    let mut cell = layer;
+                    cell[0] = x;
+                    cell[1] = y;
+                    if active.binary_search(&cell).is_ok() {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        });
+
+        slices.push(
+            std::iter::once(header)
+                .filter(|header| !header.is_empty())
+                .chain(rows)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        // Count through the layers like an odometer, starting at z.
+        match (2..DIMENSIONS).find(|&idx| layer[idx] < max[idx]) {
+            Some(idx) => {
+                layer[idx] += 1;
+                layer[2..idx].copy_from_slice(&min[2..idx]);
+            }
+            None => break,
+        }
+    }
+
+    slices.join("\n\n")
+}
+
+/// The bounding box as `x=0..2, y=1..3, z=-1..1`.
+pub fn format_bounds<const DIMENSIONS: usize>(
+    (min, max): (Coord<DIMENSIONS>, Coord<DIMENSIONS>),
+) -> String {
+    (0..DIMENSIONS)
+        .map(|idx| format!("{}={}..{}", AXES[idx], min[idx], max[idx]))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn write_csv_header(dimensions: usize, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "cycle,{}", AXES[..dimensions].join(","))
+}
+
+/// One `cycle,x,y,…` row per active cell.
+pub fn write_csv<const DIMENSIONS: usize>(
+    cycle: usize,
+    conway: &Conway<DIMENSIONS>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    for cell in conway.active_cells() {
+        let coords = cell.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        writeln!(out, "{},{}", cycle, coords.join(","))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const EXAMPLE: &str = ".#.\n..#\n###";
+
+    #[test]
+    fn puzzle_slices() {
+        let mut conway: Conway<3> = Conway::from_2d(parse_input(EXAMPLE));
+        assert_eq!(render(&conway), "z=0\n.#.\n..#\n###");
+
+        conway.run(1);
+        assert_eq!(
+            render(&conway),
+            "z=-1\n#..\n..#\n.#.\n\nz=0\n#.#\n.##\n.#.\n\nz=1\n#..\n..#\n.#."
+        );
+        assert_eq!(
+            conway.bounding_box().map(format_bounds),
+            Some("x=0..2, y=1..3, z=-1..1".to_string())
+        );
+
+        let mut conway: Conway<4> = Conway::from_2d(parse_input(EXAMPLE));
+        conway.run(1);
+        let slices = render(&conway);
+        assert!(slices.starts_with("z=-1, w=-1\n#..\n..#\n.#.\n\nz=0, w=-1\n"));
+        assert_eq!(slices.matches("w=").count(), 9);
+    }
+
+    #[test]
+    fn csv() {
+        let mut conway: Conway<3> = Conway::from_2d(parse_input(".#\n#."));
+        let mut out = vec![];
+
+        write_csv_header(3, &mut out).unwrap();
+        write_csv(0, &conway, &mut out).unwrap();
+        conway.run(1);
+        write_csv(1, &conway, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cycle,x,y,z\n0,0,1,0\n0,1,0,0\n"
+        );
+    }
+}
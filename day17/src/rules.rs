@@ -0,0 +1,150 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub enum RuleError {
+    Format(String),
+    /// `B0` would turn on every inactive cell of the infinite grid at once.
+    BirthWithoutNeighbours,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::Format(rule) => {
+                write!(f, "'{}' is not a rule like B3/S23", rule)
+            }
+            RuleError::BirthWithoutNeighbours => {
+                write!(f, "rules with B0 would activate the whole grid")
+            }
+        }
+    }
+}
+
+/// A life-like rule: the neighbour counts that activate an inactive cell and
+/// the counts that keep an active cell active.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    birth: Vec<usize>,
+    survival: Vec<usize>,
+}
+
+impl Default for Rule {
+    /// The puzzle's rule, `B3/S23`.
+    fn default() -> Self {
+        Rule {
+            birth: vec![3],
+            survival: vec![2, 3],
+        }
+    }
+}
+
+impl Rule {
+    pub fn next(&self, active: bool, neighbours: usize) -> bool {
+        if active {
+            self.survival.contains(&neighbours)
+        } else {
+            self.birth.contains(&neighbours)
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    /// Parses `B…/S…` notation, where every digit is a neighbour count.
+    /// Either part may come first and may be empty.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RuleError::Format(s.to_string());
+        let mut birth = None;
+        let mut survival = None;
+
+        for part in s.trim().split('/') {
+            let counts: Option<Vec<usize>> = part
+                .chars()
+                .skip(1)
+                .map(|c| c.to_digit(10).map(|d| d as usize))
+                .collect();
+            let target = match part.chars().next() {
+                Some('B') | Some('b') => &mut birth,
+                Some('S') | Some('s') => &mut survival,
+                _ => return Err(invalid()),
+            };
+
+            if target.is_some() {
+                return Err(invalid());
+            }
+            *target = Some(counts.ok_or_else(invalid)?);
+        }
+
+        match (birth, survival) {
+            (Some(birth), _) if birth.contains(&0) => Err(RuleError::BirthWithoutNeighbours),
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |counts: &[usize]| counts.iter().map(|n| n.to_string()).collect::<String>();
+
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))
+    }
+}
+
+/// Which cells count as neighbours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbourhood {
+    /// Every cell within one step along each axis, `3^D - 1` of them.
+    Moore,
+    /// Only the cells one step along a single axis, `2 * D` of them.
+    VonNeumann,
+}
+
+impl Neighbourhood {
+    pub fn contains(self, offset: &[isize]) -> bool {
+        match self {
+            Neighbourhood::Moore => true,
+            Neighbourhood::VonNeumann => offset.iter().filter(|&&d| d != 0).count() == 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("B3/S23".parse(), Ok(Rule::default()));
+        assert_eq!(
+            "s23/b36".parse::<Rule>().map(|rule| rule.to_string()),
+            Ok("B36/S23".to_string())
+        );
+        assert_eq!(
+            "B2/S".parse(),
+            Ok(Rule {
+                birth: vec![2],
+                survival: vec![]
+            })
+        );
+
+        assert_eq!(
+            "B3".parse::<Rule>(),
+            Err(RuleError::Format("B3".to_string()))
+        );
+        assert_eq!(
+            "B3/S2x".parse::<Rule>(),
+            Err(RuleError::Format("B3/S2x".to_string()))
+        );
+        assert_eq!(
+            "B3/B4".parse::<Rule>(),
+            Err(RuleError::Format("B3/B4".to_string()))
+        );
+        assert_eq!(
+            "B03/S23".parse::<Rule>(),
+            Err(RuleError::BirthWithoutNeighbours)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::report::AXES;
+use crate::Coord;
+use shared::prelude::*;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum SliceError {
+    /// A header that isn't a list of `axis=coordinate` pairs, or names an
+    /// axis the pocket dimension doesn't have.
+    Header(String),
+    /// An unexpected character and its line.
+    Char(char, usize),
+}
+
+impl fmt::Display for SliceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SliceError::Header(header) => write!(f, "invalid slice header '{}'", header),
+            SliceError::Char(c, line) => write!(f, "unexpected '{}' on line {}", c, line + 1),
+        }
+    }
+}
+
+fn parse_header<const DIMENSIONS: usize>(header: &str) -> Result<Coord<DIMENSIONS>, SliceError> {
+    let invalid = || SliceError::Header(header.to_string());
+    let mut layer = [0; DIMENSIONS];
+
+    for part in header.split(',') {
+        let mut pair = part.trim().splitn(2, '=');
+        let axis = pair.next().unwrap_or("");
+        let idx = AXES
+            .iter()
+            .position(|&name| name == axis)
+            .filter(|&idx| (2..DIMENSIONS).contains(&idx))
+            .ok_or_else(invalid)?;
+
+        layer[idx] = pair
+            .next()
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(invalid)?;
+    }
+
+    Ok(layer)
+}
+
+/// Reads active cells from 2D slices separated by blank lines, each headed
+/// by its other coordinates (`z=-1, w=0`) like the puzzle prints them.
+/// Coordinates that aren't given are 0, so plain puzzle input or a 3D state
+/// can seed any number of dimensions. Lines ending in `:`, like `After 2
+/// cycles:`, are skipped.
+pub fn parse<const DIMENSIONS: usize>(
+    input: &str,
+) -> Result<HashSet<Coord<DIMENSIONS>>, SliceError> {
+    let mut cells = HashSet::new();
+    let mut layer = [0; DIMENSIONS];
+    let mut y = 0;
+
+    for (line_idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.ends_with(':') {
+            continue;
+        }
+        if line.contains('=') {
+            layer = parse_header(line)?;
+            y = 0;
+            continue;
+        }
+
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '#' => {
+                    let mut cell = layer;
+                    cell[0] = x as isize;
+                    cell[1] = y;
+                    cells.insert(cell);
+                }
+                '.' => {}
+                c => return Err(SliceError::Char(c, line_idx)),
+            }
+        }
+        y += 1;
+    }
+
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices() {
+        let cells: HashSet<Coord<4>> = parse("z=-1, w=2\n#.\n.#\n\nw=1\n.#").unwrap();
+
+        assert_eq!(
+            cells.into_iter().sorted().collect::<Vec<_>>(),
+            vec![[0, 0, -1, 2], [1, 0, 0, 1], [1, 1, -1, 2]]
+        );
+
+        assert_eq!(
+            parse::<3>("z=0, w=1\n#"),
+            Err(SliceError::Header("z=0, w=1".to_string()))
+        );
+        assert_eq!(
+            parse::<3>("z=one\n#"),
+            Err(SliceError::Header("z=one".to_string()))
+        );
+        assert_eq!(parse::<3>("z=0\n#.\n.o"), Err(SliceError::Char('o', 2)));
+    }
+}
@@ -0,0 +1,233 @@
+use day18::error::Error;
+use day18::expr;
+use day18::operator::{Associativity, Operators};
+use day18::Value;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+Enter an expression to evaluate it. Results are stored as $1, $2, ...
+
+Commands:
+  :mode a          apply all operators left to right (part A)
+  :mode b          apply addition before multiplication (part B)
+  :prec <op> <n>   set the precedence of an infix operator
+  :tree <expr>     show how an expression is grouped
+  :ops             list the operators and their precedence
+  :history         list the previous results
+  :help            show this message
+  :quit            exit";
+
+struct Session {
+    operators: Operators,
+    history: Vec<Value>,
+}
+
+fn report(source: &str, error: &Error) -> String {
+    let width = error.span.end.saturating_sub(error.span.start).max(1);
+
+    format!(
+        "error: {}\n  {}\n  {}{}",
+        error,
+        source,
+        " ".repeat(error.span.start),
+        "^".repeat(width)
+    )
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            operators: Operators::left_to_right(),
+            history: vec![],
+        }
+    }
+
+    /// Handles a single line of input and returns the text to show, or `None`
+    /// when the session should end.
+    fn handle(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+
+        if !line.starts_with(':') {
+            return Some(self.evaluate(line));
+        }
+
+        let mut parts = line[1..].splitn(2, ' ');
+        let command = parts.next().unwrap_or("");
+        let args = parts.next().unwrap_or("").trim();
+
+        let output = match (command, args) {
+            ("quit", _) | ("q", _) => return None,
+            ("help", _) => HELP.to_string(),
+            ("mode", "a") => {
+                self.operators = Operators::left_to_right();
+                "operators are applied left to right".to_string()
+            }
+            ("mode", "b") => {
+                self.operators = Operators::addition_first();
+                "addition is applied before multiplication".to_string()
+            }
+            ("mode", _) => "usage: :mode a|b".to_string(),
+            ("prec", args) => self.set_precedence(args),
+            ("tree", expression) => match expr::parse(expression, &self.operators) {
+                Ok(expr) => expr.to_string(),
+                Err(error) => report(expression, &error),
+            },
+            ("ops", _) => self.list_operators(),
+            ("history", _) => self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| format!("${} = {}", idx + 1, value))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => format!("unknown command ':{}', try :help", command),
+        };
+
+        Some(output)
+    }
+
+    fn evaluate(&mut self, expression: &str) -> String {
+        if expression.is_empty() {
+            return String::new();
+        }
+
+        let result = expr::parse(expression, &self.operators)
+            .and_then(|expr| expr.evaluate_with(&self.history));
+
+        match result {
+            Ok(value) => {
+                self.history.push(value);
+                format!("${} = {}", self.history.len(), value)
+            }
+            Err(error) => report(expression, &error),
+        }
+    }
+
+    fn set_precedence(&mut self, args: &str) -> String {
+        let mut parts = args.split_whitespace();
+        let symbol = parts.next().and_then(|s| s.chars().next());
+        let precedence = parts.next().and_then(|s| s.parse().ok());
+
+        match (symbol, precedence) {
+            (Some(symbol), Some(precedence)) => {
+                if self.operators.set_precedence(symbol, precedence) {
+                    format!("'{}' now has precedence {}", symbol, precedence)
+                } else {
+                    format!("unknown operator '{}'", symbol)
+                }
+            }
+            _ => "usage: :prec <op> <n>".to_string(),
+        }
+    }
+
+    fn list_operators(&self) -> String {
+        let mut lines: Vec<(char, String)> = self
+            .operators
+            .infix_operators()
+            .map(|(symbol, op)| {
+                let associativity = match op.get_associativity() {
+                    Associativity::Left => "left",
+                    Associativity::Right => "right",
+                };
+                let line = format!(
+                    "{}  precedence {}, {}",
+                    symbol,
+                    self.operators.precedence(symbol).unwrap_or(0),
+                    associativity
+                );
+
+                (symbol, line)
+            })
+            .chain(self.operators.prefix.iter().map(|(&symbol, op)| {
+                let line = format!("{}  precedence {}, prefix", symbol, op.get_precedence());
+
+                (symbol, line)
+            }))
+            .collect();
+        lines.sort();
+
+        lines
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn prompt() {
+    print!("> ");
+    io::stdout().flush().unwrap();
+}
+
+fn main() {
+    let mut session = Session::new();
+    let stdin = io::stdin();
+
+    println!("day18 calculator, :help lists the commands");
+    prompt();
+
+    for line in stdin.lock().lines() {
+        match session.handle(&line.unwrap()) {
+            Some(output) if output.is_empty() => {}
+            Some(output) => println!("{}", output),
+            None => return,
+        }
+        prompt();
+    }
+
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_and_history() {
+        let mut session = Session::new();
+
+        assert_eq!(session.handle("2 * 3 + 1"), Some("$1 = 7".to_string()));
+        assert_eq!(
+            session.handle(":tree 2 * 3 + 1"),
+            Some("((2 * 3) + 1)".to_string())
+        );
+
+        session.handle(":mode b");
+        assert_eq!(session.handle("2 * 3 + 1"), Some("$2 = 8".to_string()));
+        assert_eq!(
+            session.handle(":tree $1 + 2 * 3"),
+            Some("(($1 + 2) * 3)".to_string())
+        );
+        assert_eq!(session.handle("$1 - $2"), Some("$3 = -1".to_string()));
+
+        session.handle(":prec * 20");
+        assert_eq!(
+            session.handle(":tree 1 + 2 * 3"),
+            Some("(1 + (2 * 3))".to_string())
+        );
+        assert_eq!(
+            session.handle(":history"),
+            Some("$1 = 7\n$2 = 8\n$3 = -1".to_string())
+        );
+        assert_eq!(session.handle(":quit"), None);
+    }
+
+    #[test]
+    fn errors() {
+        let mut session = Session::new();
+
+        assert_eq!(
+            session.handle("2 * (3 + $4"),
+            Some("error: '(' is never closed at column 5\n  2 * (3 + $4\n      ^".to_string())
+        );
+        assert_eq!(
+            session.handle("2 * $4"),
+            Some("error: unknown variable at column 5\n  2 * $4\n      ^^".to_string())
+        );
+        assert_eq!(
+            session.handle(":prec % 3"),
+            Some("unknown operator '%'".to_string())
+        );
+        assert!(session.history.is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::lexer::Span;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    UnexpectedChar(char),
+    UnknownOperator(char),
+    LiteralOverflow,
+    UnknownVariable,
+    ExpectedOperand,
+    ExpectedOperator,
+    UnclosedParen,
+    UnmatchedParen,
+    Overflow,
+    DivisionByZero,
+    NegativeExponent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub span: Span,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, span: Span) -> Self {
+        Error { kind, span }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            ErrorKind::UnknownOperator(c) => write!(f, "unknown operator '{}'", c),
+            ErrorKind::LiteralOverflow => write!(f, "number literal is too large"),
+            ErrorKind::UnknownVariable => write!(f, "unknown variable"),
+            ErrorKind::ExpectedOperand => write!(f, "expected a number, variable or '('"),
+            ErrorKind::ExpectedOperator => write!(f, "expected an operator or ')'"),
+            ErrorKind::UnclosedParen => write!(f, "'(' is never closed"),
+            ErrorKind::UnmatchedParen => write!(f, "')' has no matching '('"),
+            ErrorKind::Overflow => write!(f, "arithmetic overflow"),
+            ErrorKind::DivisionByZero => write!(f, "division by zero"),
+            ErrorKind::NegativeExponent => write!(f, "negative exponent"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.kind, self.span.start + 1)
+    }
+}
+
+impl std::error::Error for Error {}
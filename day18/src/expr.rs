@@ -0,0 +1,281 @@
+use crate::error::{Error, ErrorKind};
+use crate::lexer::{self, Span, TokenKind};
+use crate::operator::{Associativity, Operator, Operators, PrefixOperator};
+use crate::Value;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Expr<'a> {
+    Number(Value),
+    Variable(usize, Span),
+    Prefix {
+        symbol: char,
+        operator: &'a dyn PrefixOperator,
+        span: Span,
+        operand: Box<Expr<'a>>,
+    },
+    Binary {
+        symbol: char,
+        operator: &'a dyn Operator,
+        span: Span,
+        lhs: Box<Expr<'a>>,
+        rhs: Box<Expr<'a>>,
+    },
+}
+
+impl<'a> Expr<'a> {
+    pub fn evaluate(&self) -> Result<Value, Error> {
+        self.evaluate_with(&[])
+    }
+
+    /// Evaluates with `$1`, `$2`, ... bound to `variables`. Arithmetic errors
+    /// point at the operator that caused them.
+    pub fn evaluate_with(&self, variables: &[Value]) -> Result<Value, Error> {
+        match self {
+            Expr::Number(n) => Ok(*n),
+            Expr::Variable(index, span) => index
+                .checked_sub(1)
+                .and_then(|idx| variables.get(idx))
+                .copied()
+                .ok_or_else(|| Error::new(ErrorKind::UnknownVariable, *span)),
+            Expr::Prefix {
+                operator,
+                span,
+                operand,
+                ..
+            } => operator
+                .evaluate(operand.evaluate_with(variables)?)
+                .map_err(|kind| Error::new(kind, *span)),
+            Expr::Binary {
+                operator,
+                span,
+                lhs,
+                rhs,
+                ..
+            } => operator
+                .evaluate(lhs.evaluate_with(variables)?, rhs.evaluate_with(variables)?)
+                .map_err(|kind| Error::new(kind, *span)),
+        }
+    }
+}
+
+/// Prints every operation wrapped in parentheses, so the grouping chosen by
+/// the precedence table is visible: `1 * 2 + 3` prints as `((1 * 2) + 3)`
+/// under part A's table and as `(1 * (2 + 3))` under part B's.
+impl<'a> fmt::Display for Expr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Variable(index, _) => write!(f, "${}", index),
+            Expr::Prefix {
+                symbol, operand, ..
+            } => write!(f, "({}{})", symbol, operand),
+            Expr::Binary {
+                symbol, lhs, rhs, ..
+            } => write!(f, "({} {} {})", lhs, symbol, rhs),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum OpEntry<'a> {
+    Infix(char, &'a dyn Operator, Span),
+    Prefix(char, &'a dyn PrefixOperator, Span),
+    Bracket(Span),
+}
+
+// The token loop in `parse` only lets an operator through when its operands
+// are in place, so the output stack can't run dry here.
+fn apply_operator<'a>(output_stack: &mut Vec<Expr<'a>>, entry: OpEntry<'a>) {
+    let expr = match entry {
+        OpEntry::Infix(symbol, operator, span) => {
+            let rhs = output_stack.pop().unwrap();
+            let lhs = output_stack.pop().unwrap();
+
+            Expr::Binary {
+                symbol,
+                operator,
+                span,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            }
+        }
+        OpEntry::Prefix(symbol, operator, span) => Expr::Prefix {
+            symbol,
+            operator,
+            span,
+            operand: Box::new(output_stack.pop().unwrap()),
+        },
+        OpEntry::Bracket(_) => unreachable!(),
+    };
+
+    output_stack.push(expr);
+}
+
+fn binds_before(prev: &OpEntry, cur: char, cur_op: &dyn Operator, operators: &Operators) -> bool {
+    let prev_precedence = match prev {
+        OpEntry::Infix(symbol, _, _) => operators.precedence(*symbol).unwrap_or(0),
+        OpEntry::Prefix(_, op, _) => op.get_precedence(),
+        OpEntry::Bracket(_) => return false,
+    };
+    let precedence = operators.precedence(cur).unwrap_or(0);
+
+    match cur_op.get_associativity() {
+        Associativity::Left => prev_precedence >= precedence,
+        Associativity::Right => prev_precedence > precedence,
+    }
+}
+
+pub fn parse<'a>(s: &str, operators: &'a Operators) -> Result<Expr<'a>, Error> {
+    let mut output_stack = vec![];
+    let mut op_stack: Vec<OpEntry> = vec![];
+    let mut expect_operand = true;
+
+    for token in lexer::tokenize(s)? {
+        match (token.kind, expect_operand) {
+            (TokenKind::Number(n), true) => {
+                output_stack.push(Expr::Number(n));
+                expect_operand = false;
+            }
+            (TokenKind::Variable(index), true) => {
+                output_stack.push(Expr::Variable(index, token.span));
+                expect_operand = false;
+            }
+            (TokenKind::Open, true) => op_stack.push(OpEntry::Bracket(token.span)),
+            (TokenKind::Operator(c), true) => {
+                let op = match operators.prefix.get(&c) {
+                    Some(op) => op,
+                    None if operators.infix(c).is_some() => {
+                        return Err(Error::new(ErrorKind::ExpectedOperand, token.span))
+                    }
+                    None => return Err(Error::new(ErrorKind::UnknownOperator(c), token.span)),
+                };
+
+                op_stack.push(OpEntry::Prefix(c, op.as_ref(), token.span));
+            }
+            (TokenKind::Operator(c), false) => {
+                let cur_op = operators
+                    .infix(c)
+                    .ok_or_else(|| Error::new(ErrorKind::UnknownOperator(c), token.span))?;
+
+                while let Some(prev) = op_stack.last() {
+                    if binds_before(prev, c, cur_op, operators) {
+                        let prev = op_stack.pop().unwrap();
+                        apply_operator(&mut output_stack, prev);
+                    } else {
+                        break;
+                    }
+                }
+                op_stack.push(OpEntry::Infix(c, cur_op, token.span));
+                expect_operand = true;
+            }
+            (TokenKind::Close, false) => loop {
+                match op_stack.pop() {
+                    Some(OpEntry::Bracket(_)) => break,
+                    Some(entry) => apply_operator(&mut output_stack, entry),
+                    None => return Err(Error::new(ErrorKind::UnmatchedParen, token.span)),
+                }
+            },
+            (_, true) => return Err(Error::new(ErrorKind::ExpectedOperand, token.span)),
+            (_, false) => return Err(Error::new(ErrorKind::ExpectedOperator, token.span)),
+        }
+    }
+
+    if expect_operand {
+        let end = s.trim_end().chars().count();
+        return Err(Error::new(ErrorKind::ExpectedOperand, Span::at(end)));
+    }
+
+    while let Some(entry) = op_stack.pop() {
+        match entry {
+            OpEntry::Bracket(span) => return Err(Error::new(ErrorKind::UnclosedParen, span)),
+            entry => apply_operator(&mut output_stack, entry),
+        }
+    }
+
+    Ok(output_stack.pop().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::*;
+
+    #[test]
+    fn grouping() {
+        let mut operators = Operators::new();
+        operators.insert_infix('+', Box::new(Add));
+        operators.insert_infix('*', Box::new(Mul));
+
+        let expr = parse("1 + 2 * 3 + (4 * 5)", &operators).unwrap();
+        assert_eq!(expr.to_string(), "(((1 + 2) * 3) + (4 * 5))");
+        assert_eq!(expr.evaluate(), Ok(29));
+
+        operators.insert_infix('+', Box::new(PrioAdd));
+
+        let expr = parse("1 + 2 * 3 + (4 * 5)", &operators).unwrap();
+        assert_eq!(expr.to_string(), "((1 + 2) * (3 + (4 * 5)))");
+        assert_eq!(expr.evaluate(), Ok(69));
+    }
+
+    #[test]
+    fn single_number() {
+        let operators = Operators::new();
+
+        let expr = parse("((42))", &operators).unwrap();
+        assert_eq!(expr.to_string(), "42");
+        assert_eq!(expr.evaluate(), Ok(42));
+    }
+
+    #[test]
+    fn associativity_and_prefix() {
+        let operators = Operators::left_to_right();
+        let group = |s| parse(s, &operators).unwrap().to_string();
+
+        assert_eq!(group("2 ^ 3 ^ 2"), "(2 ^ (3 ^ 2))");
+        assert_eq!(group("8 - 3 - 2"), "((8 - 3) - 2)");
+        assert_eq!(group("-2 ^ 2"), "(-(2 ^ 2))");
+        assert_eq!(group("-2 + 3"), "((-2) + 3)");
+        assert_eq!(group("2 * --3"), "(2 * (-(-3)))");
+        assert_eq!(group("2 ^ -(1 - 2)"), "(2 ^ (-(1 - 2)))");
+
+        assert_eq!(parse("2 ^ 3 ^ 2", &operators).unwrap().evaluate(), Ok(512));
+        assert_eq!(parse("-2 ^ 2", &operators).unwrap().evaluate(), Ok(-4));
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        let operators = Operators::left_to_right();
+        let evaluate = |s| parse(s, &operators).unwrap().evaluate();
+
+        assert_eq!(
+            evaluate("1 + 6 / (3 - 3)"),
+            Err(Error::new(ErrorKind::DivisionByZero, Span::at(6)))
+        );
+        assert_eq!(
+            evaluate("10 ^ 38 * 2"),
+            Err(Error::new(ErrorKind::Overflow, Span::at(8)))
+        );
+        assert_eq!(
+            parse("1 * * 2", &operators).unwrap_err(),
+            Error::new(ErrorKind::ExpectedOperand, Span::at(4))
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let operators = Operators::left_to_right();
+        let expr = parse("$1 * ($2 - 1)", &operators).unwrap();
+
+        assert_eq!(expr.to_string(), "($1 * ($2 - 1))");
+        assert_eq!(expr.evaluate_with(&[6, 8]), Ok(42));
+        assert_eq!(
+            expr.evaluate_with(&[6]),
+            Err(Error::new(ErrorKind::UnknownVariable, Span::new(6, 8)))
+        );
+        assert_eq!(
+            parse("$0", &operators).unwrap().evaluate_with(&[1]),
+            Err(Error::new(ErrorKind::UnknownVariable, Span::new(0, 2)))
+        );
+    }
+}
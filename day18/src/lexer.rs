@@ -0,0 +1,133 @@
+use crate::error::{Error, ErrorKind};
+use crate::Value;
+use std::convert::TryFrom;
+use std::iter::Peekable;
+
+/// Character columns `start..end` (0-based) of a token in its source line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    pub fn at(column: usize) -> Self {
+        Span::new(column, column + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Number(Value),
+    Variable(usize),
+    Operator(char),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+// Consumes the digits following `value` and returns the resulting number, or
+// `None` if it overflows, together with the column after the last digit.
+fn take_digits<I>(chars: &mut Peekable<I>, mut end: usize, value: Value) -> (Option<Value>, usize)
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    let mut value = Some(value);
+
+    while let Some(digit) = chars.peek().and_then(|&(_, d)| d.to_digit(10)) {
+        value = value
+            .and_then(|v| v.checked_mul(10))
+            .and_then(|v| v.checked_add(digit as Value));
+        end += 1;
+        chars.next();
+    }
+
+    (value, end)
+}
+
+pub fn tokenize(s: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().enumerate().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let (kind, end) = match c {
+            c if c.is_whitespace() => continue,
+            '0'..='9' => {
+                let (value, end) =
+                    take_digits(&mut chars, start + 1, c.to_digit(10).unwrap() as Value);
+                let value = value
+                    .ok_or_else(|| Error::new(ErrorKind::LiteralOverflow, Span::new(start, end)))?;
+
+                (TokenKind::Number(value), end)
+            }
+            '$' if chars.peek().map_or(false, |&(_, d)| d.is_ascii_digit()) => {
+                let (index, end) = take_digits(&mut chars, start + 1, 0);
+                let index = index
+                    .and_then(|i| usize::try_from(i).ok())
+                    .ok_or_else(|| Error::new(ErrorKind::UnknownVariable, Span::new(start, end)))?;
+
+                (TokenKind::Variable(index), end)
+            }
+            '(' => (TokenKind::Open, start + 1),
+            ')' => (TokenKind::Close, start + 1),
+            c if c.is_ascii_punctuation() && c != '$' => (TokenKind::Operator(c), start + 1),
+            c => return Err(Error::new(ErrorKind::UnexpectedChar(c), Span::at(start))),
+        };
+
+        tokens.push(Token {
+            kind,
+            span: Span::new(start, end),
+        });
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_digit_numbers() {
+        let tokens = tokenize("12 + (345) * $2").unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Number(12),
+                TokenKind::Operator('+'),
+                TokenKind::Open,
+                TokenKind::Number(345),
+                TokenKind::Close,
+                TokenKind::Operator('*'),
+                TokenKind::Variable(2),
+            ]
+        );
+        assert_eq!(tokens[0].span, Span::new(0, 2));
+        assert_eq!(tokens[3].span, Span::new(6, 9));
+        assert_eq!(tokens[6].span, Span::new(13, 15));
+    }
+
+    #[test]
+    fn invalid_input() {
+        let err = tokenize("1 + x").unwrap_err();
+        assert_eq!(err, Error::new(ErrorKind::UnexpectedChar('x'), Span::at(4)));
+
+        let err = tokenize("1 * 999999999999999999999999999999999999999999").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::LiteralOverflow);
+        assert_eq!(err.span, Span::new(4, 46));
+
+        let err = tokenize("$ + 1").unwrap_err();
+        assert_eq!(err, Error::new(ErrorKind::UnexpectedChar('$'), Span::at(0)));
+    }
+}
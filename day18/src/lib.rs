@@ -0,0 +1,13 @@
+pub mod error;
+pub mod expr;
+pub mod lexer;
+pub mod operator;
+
+use error::Error;
+use operator::Operators;
+
+pub type Value = i128;
+
+pub fn evaluate(s: &str, operators: &Operators) -> Result<Value, Error> {
+    expr::parse(s, operators)?.evaluate()
+}
@@ -2,126 +2,34 @@
 #[allow(unused_imports)]
 use shared::prelude::*;
 
-const INPUT: &'static str = include_str!("./input.txt");
+use day18::evaluate;
+use day18::operator::Operators;
+
+const INPUT: &str = include_str!("./input.txt");
 
 type Data<'a> = Vec<&'a str>;
-type Solution = u64;
+type Solution = day18::Value;
 
 fn parse_input(input: &str) -> Data {
     input.lines().collect()
 }
 
-trait Operator: std::fmt::Debug {
-    fn evaluate(&self, lhs: u64, rhs: u64) -> u64;
-
-    fn get_precedence(&self) -> u64 {
-        0
-    }
-}
-#[derive(Debug)]
-struct Add;
-#[derive(Debug)]
-struct Mul;
-
-impl Operator for Add {
-    fn evaluate(&self, lhs: u64, rhs: u64) -> u64 {
-        lhs + rhs
-    }
-}
-
-impl Operator for Mul {
-    fn evaluate(&self, lhs: u64, rhs: u64) -> u64 {
-        lhs * rhs
-    }
-}
-
-#[derive(Debug)]
-struct PrioAdd;
-
-impl Operator for PrioAdd {
-    fn evaluate(&self, lhs: u64, rhs: u64) -> u64 {
-        lhs + rhs
-    }
-
-    fn get_precedence(&self) -> u64 {
-        10
-    }
-}
-
-#[derive(Debug)]
-enum OpEntry<'a> {
-    Operator(&'a Box<dyn Operator>),
-    Bracket,
-}
-
-fn pop_operator(output_stack: &mut Vec<u64>, op_stack: &mut Vec<OpEntry>) {
-    let rhs = output_stack.pop().unwrap();
-    let lhs = output_stack.pop().unwrap();
-    let operator = op_stack.pop().unwrap();
-
-    if let OpEntry::Operator(operator) = operator {
-        output_stack.push(operator.evaluate(lhs, rhs));
-    }
-}
-
-fn evaluate(s: &str, operators: &HashMap<char, Box<dyn Operator>>) -> u64 {
-    let mut output_stack = vec![];
-    let mut op_stack: Vec<OpEntry> = vec![];
-
-    for c in s.chars() {
-        match c {
-            ' ' => {}
-            '0'..='9' => {
-                output_stack.push(c.to_digit(10).unwrap() as u64);
-            }
-            c if operators.contains_key(&c) => {
-                let cur_op = operators.get(&c).unwrap();
-
-                while let Some(OpEntry::Operator(prev_op)) = op_stack.last() {
-                    if prev_op.get_precedence() >= cur_op.get_precedence() {
-                        pop_operator(&mut output_stack, &mut op_stack);
-                    } else {
-                        break;
-                    }
-                }
-                op_stack.push(OpEntry::Operator(cur_op));
-            }
-            '(' => op_stack.push(OpEntry::Bracket),
-            ')' => loop {
-                match op_stack.last() {
-                    Some(OpEntry::Bracket) => {
-                        op_stack.pop();
-                        break;
-                    }
-                    Some(_) => pop_operator(&mut output_stack, &mut op_stack),
-                    _ => break,
-                }
-            },
-            c => panic!("INVALID CHAR: {}", c),
-        }
-    }
-
-    while op_stack.len() > 0 {
-        pop_operator(&mut output_stack, &mut op_stack);
-    }
-
-    output_stack[0]
-}
-
-fn solve_a(data: &Data) -> Solution {
-    let mut operators: HashMap<char, Box<dyn Operator>> = HashMap::new();
-    operators.insert('+', Box::new(Add));
-    operators.insert('*', Box::new(Mul));
+fn solve_a(data: &[&str]) -> Solution {
+    let operators = Operators::left_to_right();
 
-    data.iter().map(|s| evaluate(*s, &operators)).sum()
+    data.iter()
+        .map(|s| evaluate(s, &operators))
+        .sum::<Result<_, _>>()
+        .unwrap()
 }
 
-fn solve_b(data: &Data) -> Solution {
-    let mut operators: HashMap<char, Box<dyn Operator>> = HashMap::new();
-    operators.insert('+', Box::new(PrioAdd));
-    operators.insert('*', Box::new(Mul));
+fn solve_b(data: &[&str]) -> Solution {
+    let operators = Operators::addition_first();
 
-    data.iter().map(|s| evaluate(*s, &operators)).sum()
+    data.iter()
+        .map(|s| evaluate(s, &operators))
+        .sum::<Result<_, _>>()
+        .unwrap()
 }
 
 fn main() {
@@ -134,25 +42,83 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use day18::error::{Error, ErrorKind};
+    use day18::lexer::Span;
+    use day18::operator::{Add, Mul, PrioAdd};
 
     #[test]
     fn solve_a() {
-        let mut operators: HashMap<char, Box<dyn Operator>> = HashMap::new();
-        operators.insert('+', Box::new(Add));
-        operators.insert('*', Box::new(Mul));
+        let mut operators = Operators::new();
+        operators.insert_infix('+', Box::new(Add));
+        operators.insert_infix('*', Box::new(Mul));
 
-        assert_eq!(evaluate("5 + (8 * 3 + 9 + 3 * 4 * 3)", &operators), 437);
+        assert_eq!(evaluate("5 + (8 * 3 + 9 + 3 * 4 * 3)", &operators), Ok(437));
     }
 
     #[test]
     fn solve_b() {
-        let mut operators: HashMap<char, Box<dyn Operator>> = HashMap::new();
-        operators.insert('+', Box::new(PrioAdd));
-        operators.insert('*', Box::new(Mul));
+        let mut operators = Operators::new();
+        operators.insert_infix('+', Box::new(PrioAdd));
+        operators.insert_infix('*', Box::new(Mul));
 
         assert_eq!(
             evaluate("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", &operators),
-            669060
+            Ok(669060)
+        );
+    }
+
+    #[test]
+    fn multi_digit_numbers() {
+        let mut operators = Operators::new();
+        operators.insert_infix('+', Box::new(Add));
+        operators.insert_infix('*', Box::new(Mul));
+
+        assert_eq!(evaluate("12 + 3", &operators), Ok(15));
+        assert_eq!(
+            evaluate("(10000000000 * 10000000000) + 1", &operators),
+            Ok(100000000000000000001)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut operators = Operators::new();
+        operators.insert_infix('+', Box::new(Add));
+        operators.insert_infix('*', Box::new(Mul));
+
+        let error = |kind, start, end| Err(Error::new(kind, Span::new(start, end)));
+
+        assert_eq!(
+            evaluate("1 + (2 * 3", &operators),
+            error(ErrorKind::UnclosedParen, 4, 5)
+        );
+        assert_eq!(
+            evaluate("1 + 2) * 3", &operators),
+            error(ErrorKind::UnmatchedParen, 5, 6)
+        );
+        assert_eq!(
+            evaluate("1 + * 3", &operators),
+            error(ErrorKind::ExpectedOperand, 4, 5)
+        );
+        assert_eq!(
+            evaluate("1 2", &operators),
+            error(ErrorKind::ExpectedOperator, 2, 3)
+        );
+        assert_eq!(
+            evaluate("1 +", &operators),
+            error(ErrorKind::ExpectedOperand, 3, 4)
+        );
+        assert_eq!(
+            evaluate("()", &operators),
+            error(ErrorKind::ExpectedOperand, 1, 2)
+        );
+        assert_eq!(
+            evaluate("1 % 2", &operators),
+            error(ErrorKind::UnknownOperator('%'), 2, 3)
+        );
+        assert_eq!(
+            evaluate("1 + a", &operators),
+            error(ErrorKind::UnexpectedChar('a'), 4, 5)
         );
     }
 }
@@ -0,0 +1,234 @@
+use crate::error::ErrorKind;
+use crate::Value;
+use shared::prelude::*;
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+pub trait Operator: Debug {
+    fn evaluate(&self, lhs: Value, rhs: Value) -> Result<Value, ErrorKind>;
+
+    fn get_precedence(&self) -> u64 {
+        0
+    }
+
+    fn get_associativity(&self) -> Associativity {
+        Associativity::Left
+    }
+}
+
+pub trait PrefixOperator: Debug {
+    fn evaluate(&self, operand: Value) -> Result<Value, ErrorKind>;
+
+    /// Binary operators with a higher precedence than this are applied to the
+    /// operand first, so `-2 ^ 2` is `-(2 ^ 2)`.
+    fn get_precedence(&self) -> u64 {
+        15
+    }
+}
+
+/// The operator table used by the parser. Infix and prefix operators live in
+/// separate maps so the same symbol (`-`) can be both.
+#[derive(Debug, Default)]
+pub struct Operators {
+    infix: HashMap<char, Box<dyn Operator>>,
+    pub prefix: HashMap<char, Box<dyn PrefixOperator>>,
+    /// Infix precedences set with `set_precedence`, used instead of the
+    /// operator's own.
+    precedence: HashMap<char, u64>,
+}
+
+impl Operators {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Part A's rules: `+ - * /` share one precedence and are applied left to
+    /// right.
+    pub fn left_to_right() -> Self {
+        let mut operators = Operators::new();
+        operators.insert_infix('+', Box::new(Add));
+        operators.insert_infix('-', Box::new(Sub));
+        operators.insert_infix('*', Box::new(Mul));
+        operators.insert_infix('/', Box::new(Div));
+        operators.insert_infix('^', Box::new(Pow));
+        operators.prefix.insert('-', Box::new(Neg));
+
+        operators
+    }
+
+    /// Part B's rules: addition and subtraction are applied before
+    /// multiplication and division.
+    pub fn addition_first() -> Self {
+        let mut operators = Operators::left_to_right();
+        operators.insert_infix('+', Box::new(PrioAdd));
+        operators.set_precedence('-', PrioAdd.get_precedence());
+
+        operators
+    }
+
+    /// Adds the infix operator for `symbol`, replacing any earlier one along
+    /// with the precedence set for it.
+    pub fn insert_infix(&mut self, symbol: char, operator: Box<dyn Operator>) {
+        self.infix.insert(symbol, operator);
+        self.precedence.remove(&symbol);
+    }
+
+    pub fn infix(&self, symbol: char) -> Option<&dyn Operator> {
+        self.infix.get(&symbol).map(|operator| operator.as_ref())
+    }
+
+    /// Every infix operator, in no particular order.
+    pub fn infix_operators(&self) -> impl Iterator<Item = (char, &dyn Operator)> {
+        self.infix
+            .iter()
+            .map(|(&symbol, operator)| (symbol, operator.as_ref()))
+    }
+
+    /// The precedence of the infix operator for `symbol`.
+    pub fn precedence(&self, symbol: char) -> Option<u64> {
+        let operator = self.infix.get(&symbol)?;
+
+        Some(
+            self.precedence
+                .get(&symbol)
+                .copied()
+                .unwrap_or_else(|| operator.get_precedence()),
+        )
+    }
+
+    /// Replaces any earlier precedence of `symbol`. Returns `false` if there
+    /// is no infix operator for `symbol`.
+    pub fn set_precedence(&mut self, symbol: char, precedence: u64) -> bool {
+        if self.infix.contains_key(&symbol) {
+            self.precedence.insert(symbol, precedence);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Add;
+#[derive(Debug)]
+pub struct Sub;
+#[derive(Debug)]
+pub struct Mul;
+#[derive(Debug)]
+pub struct Div;
+
+impl Operator for Add {
+    fn evaluate(&self, lhs: Value, rhs: Value) -> Result<Value, ErrorKind> {
+        lhs.checked_add(rhs).ok_or(ErrorKind::Overflow)
+    }
+}
+
+impl Operator for Sub {
+    fn evaluate(&self, lhs: Value, rhs: Value) -> Result<Value, ErrorKind> {
+        lhs.checked_sub(rhs).ok_or(ErrorKind::Overflow)
+    }
+}
+
+impl Operator for Mul {
+    fn evaluate(&self, lhs: Value, rhs: Value) -> Result<Value, ErrorKind> {
+        lhs.checked_mul(rhs).ok_or(ErrorKind::Overflow)
+    }
+}
+
+impl Operator for Div {
+    fn evaluate(&self, lhs: Value, rhs: Value) -> Result<Value, ErrorKind> {
+        if rhs == 0 {
+            return Err(ErrorKind::DivisionByZero);
+        }
+
+        lhs.checked_div(rhs).ok_or(ErrorKind::Overflow)
+    }
+}
+
+#[derive(Debug)]
+pub struct PrioAdd;
+
+impl Operator for PrioAdd {
+    fn evaluate(&self, lhs: Value, rhs: Value) -> Result<Value, ErrorKind> {
+        Add.evaluate(lhs, rhs)
+    }
+
+    fn get_precedence(&self) -> u64 {
+        10
+    }
+}
+
+#[derive(Debug)]
+pub struct Pow;
+
+impl Operator for Pow {
+    fn evaluate(&self, lhs: Value, rhs: Value) -> Result<Value, ErrorKind> {
+        if rhs < 0 {
+            return Err(ErrorKind::NegativeExponent);
+        }
+
+        u32::try_from(rhs)
+            .ok()
+            .and_then(|exp| lhs.checked_pow(exp))
+            .ok_or(ErrorKind::Overflow)
+    }
+
+    fn get_precedence(&self) -> u64 {
+        20
+    }
+
+    fn get_associativity(&self) -> Associativity {
+        Associativity::Right
+    }
+}
+
+#[derive(Debug)]
+pub struct Neg;
+
+impl PrefixOperator for Neg {
+    fn evaluate(&self, operand: Value) -> Result<Value, ErrorKind> {
+        operand.checked_neg().ok_or(ErrorKind::Overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_arithmetic() {
+        assert_eq!(Add.evaluate(Value::MAX, 1), Err(ErrorKind::Overflow));
+        assert_eq!(Sub.evaluate(Value::MIN, 1), Err(ErrorKind::Overflow));
+        assert_eq!(Mul.evaluate(Value::MAX, 2), Err(ErrorKind::Overflow));
+        assert_eq!(Div.evaluate(7, 0), Err(ErrorKind::DivisionByZero));
+        assert_eq!(Div.evaluate(Value::MIN, -1), Err(ErrorKind::Overflow));
+        assert_eq!(Div.evaluate(-7, 2), Ok(-3));
+        assert_eq!(Pow.evaluate(2, 127), Err(ErrorKind::Overflow));
+        assert_eq!(Pow.evaluate(2, -1), Err(ErrorKind::NegativeExponent));
+        assert_eq!(Pow.evaluate(-2, 3), Ok(-8));
+        assert_eq!(Neg.evaluate(Value::MIN), Err(ErrorKind::Overflow));
+    }
+
+    #[test]
+    fn set_precedence() {
+        let mut operators = Operators::addition_first();
+        assert_eq!(operators.precedence('-'), Some(10));
+        assert_eq!(operators.infix('-').unwrap().evaluate(3, 5), Ok(-2));
+
+        assert!(operators.set_precedence('*', 30));
+        assert!(operators.set_precedence('*', 5));
+        assert_eq!(operators.precedence('*'), Some(5));
+        assert_eq!(operators.precedence('^'), Some(20));
+        assert!(!operators.set_precedence('%', 30));
+        assert_eq!(operators.precedence('%'), None);
+
+        operators.insert_infix('-', Box::new(Sub));
+        assert_eq!(operators.precedence('-'), Some(0));
+    }
+}
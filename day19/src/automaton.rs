@@ -0,0 +1,371 @@
+use crate::{Rule, RuleSet};
+use shared::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Returned by `compile` when a rule can reach itself. Holds the cycle,
+/// starting and ending with the same rule.
+#[derive(Debug, PartialEq)]
+pub struct Recursive(pub Vec<usize>);
+
+/// A deterministic automaton with state 0 as its start state. Missing
+/// transitions lead to an implicit dead state.
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    transitions: Vec<BTreeMap<char, usize>>,
+    accepting: Vec<bool>,
+}
+
+#[derive(Debug, Default)]
+struct Nfa {
+    transitions: Vec<Vec<(Option<char>, usize)>>,
+    accepting: HashSet<usize>,
+}
+
+impl Nfa {
+    fn add_state(&mut self) -> usize {
+        self.transitions.push(vec![]);
+        self.transitions.len() - 1
+    }
+
+    // Copies `dfa` into this automaton and returns the state its start state
+    // ended up as, together with the states that were accepting.
+    fn embed(&mut self, dfa: &Dfa) -> (usize, Vec<usize>) {
+        let offset = self.transitions.len();
+
+        for transitions in &dfa.transitions {
+            self.transitions.push(
+                transitions
+                    .iter()
+                    .map(|(&c, &target)| (Some(c), target + offset))
+                    .collect(),
+            );
+        }
+        let accepting = (0..dfa.len())
+            .filter(|&state| dfa.accepting[state])
+            .map(|state| state + offset)
+            .collect();
+
+        (offset, accepting)
+    }
+
+    fn closure(&self, states: impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+        let mut closure = BTreeSet::new();
+        let mut stack: Vec<usize> = states.into_iter().collect();
+
+        while let Some(state) = stack.pop() {
+            if closure.insert(state) {
+                stack.extend(
+                    self.transitions[state]
+                        .iter()
+                        .filter(|(c, _)| c.is_none())
+                        .map(|&(_, target)| target),
+                );
+            }
+        }
+
+        closure
+    }
+
+    /// Subset construction, starting from state 0.
+    fn determinize(&self) -> Dfa {
+        let start = self.closure(vec![0]);
+        let mut ids: HashMap<BTreeSet<usize>, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut dfa = Dfa {
+            transitions: vec![],
+            accepting: vec![],
+        };
+
+        ids.insert(start.clone(), 0);
+        queue.push_back(start);
+
+        while let Some(set) = queue.pop_front() {
+            let mut moves: BTreeMap<char, Vec<usize>> = BTreeMap::new();
+            for &state in &set {
+                for &(c, target) in &self.transitions[state] {
+                    if let Some(c) = c {
+                        moves.entry(c).or_default().push(target);
+                    }
+                }
+            }
+
+            let mut transitions = BTreeMap::new();
+            for (c, targets) in moves {
+                let target = self.closure(targets);
+                let next_id = ids.len();
+                let id = *ids.entry(target.clone()).or_insert_with(|| {
+                    queue.push_back(target);
+                    next_id
+                });
+                transitions.insert(c, id);
+            }
+
+            dfa.accepting
+                .push(set.iter().any(|state| self.accepting.contains(state)));
+            dfa.transitions.push(transitions);
+        }
+
+        dfa
+    }
+}
+
+impl Dfa {
+    fn single(c: char) -> Self {
+        let mut transitions = vec![BTreeMap::new(), BTreeMap::new()];
+        transitions[0].insert(c, 1);
+
+        Dfa {
+            transitions,
+            accepting: vec![false, true],
+        }
+    }
+
+    fn empty_string() -> Self {
+        Dfa {
+            transitions: vec![BTreeMap::new()],
+            accepting: vec![true],
+        }
+    }
+
+    fn concat(parts: &[Dfa]) -> Self {
+        let mut nfa = Nfa::default();
+        let mut ends = vec![nfa.add_state()];
+
+        for part in parts {
+            let (start, accepting) = nfa.embed(part);
+            for end in ends {
+                nfa.transitions[end].push((None, start));
+            }
+            ends = accepting;
+        }
+        nfa.accepting.extend(ends);
+
+        nfa.determinize().minimize()
+    }
+
+    fn union(alternatives: &[Dfa]) -> Self {
+        let mut nfa = Nfa::default();
+        let start = nfa.add_state();
+
+        for alternative in alternatives {
+            let (alt_start, accepting) = nfa.embed(alternative);
+            nfa.transitions[start].push((None, alt_start));
+            nfa.accepting.extend(accepting);
+        }
+
+        nfa.determinize().minimize()
+    }
+
+    /// Drops states that can't reach an accepting state and merges
+    /// equivalent ones by partition refinement.
+    fn minimize(&self) -> Self {
+        let mut live: Vec<bool> = self.accepting.clone();
+        loop {
+            let grown: Vec<usize> = (0..self.len())
+                .filter(|&state| !live[state])
+                .filter(|&state| self.transitions[state].values().any(|&t| live[t]))
+                .collect();
+            if grown.is_empty() {
+                break;
+            }
+            grown.into_iter().for_each(|state| live[state] = true);
+        }
+
+        let mut class: Vec<usize> = self.accepting.iter().map(|&a| a as usize).collect();
+        let mut class_count = 0;
+        loop {
+            let mut signatures: HashMap<(usize, Vec<(char, usize)>), usize> = HashMap::new();
+            let next: Vec<usize> = (0..self.len())
+                .map(|state| {
+                    let signature = (
+                        class[state],
+                        self.transitions[state]
+                            .iter()
+                            .filter(|(_, &target)| live[target])
+                            .map(|(&c, &target)| (c, class[target]))
+                            .collect(),
+                    );
+                    let next_id = signatures.len();
+                    *signatures.entry(signature).or_insert(next_id)
+                })
+                .collect();
+
+            class = next;
+            if signatures.len() == class_count {
+                break;
+            }
+            class_count = signatures.len();
+        }
+
+        // Number the classes so the start state's class comes first.
+        let mut order: HashMap<usize, usize> = HashMap::new();
+        order.insert(class[0], 0);
+        let states: Vec<usize> = (0..self.len())
+            .filter(|&state| state == 0 || live[state])
+            .collect();
+        for &state in &states {
+            let next_id = order.len();
+            order.entry(class[state]).or_insert(next_id);
+        }
+
+        let mut dfa = Dfa {
+            transitions: vec![BTreeMap::new(); order.len()],
+            accepting: vec![false; order.len()],
+        };
+        for state in states {
+            let id = order[&class[state]];
+            dfa.accepting[id] = self.accepting[state];
+            dfa.transitions[id] = self.transitions[state]
+                .iter()
+                .filter(|(_, &target)| live[target])
+                .map(|(&c, &target)| (c, order[&class[target]]))
+                .collect();
+        }
+
+        dfa
+    }
+
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_match(&self, message: &str) -> bool {
+        message
+            .chars()
+            .try_fold(0, |state, c| self.transitions[state].get(&c).copied())
+            .map_or(false, |state| self.accepting[state])
+    }
+
+    // Automata built by `compile` accept finite languages, so once dead
+    // states are trimmed the transition graph has no cycles.
+    fn count_from(&self, state: usize, memo: &mut Vec<Option<u128>>) -> u128 {
+        if let Some(count) = memo[state] {
+            return count;
+        }
+
+        let count = self.transitions[state]
+            .values()
+            .fold(self.accepting[state] as u128, |acc, &target| {
+                acc.saturating_add(self.count_from(target, memo))
+            });
+        memo[state] = Some(count);
+
+        count
+    }
+
+    /// Number of distinct strings accepted, saturating at `u128::MAX`.
+    pub fn count(&self) -> u128 {
+        self.count_from(0, &mut vec![None; self.len()])
+    }
+
+    /// Every accepted string, in lexicographic order.
+    pub fn strings(&self) -> Vec<String> {
+        let mut strings = vec![];
+        let mut stack = vec![(0, String::new())];
+
+        while let Some((state, prefix)) = stack.pop() {
+            if self.accepting[state] {
+                strings.push(prefix.clone());
+            }
+            for (&c, &target) in self.transitions[state].iter().rev() {
+                let mut next = prefix.clone();
+                next.push(c);
+                stack.push((target, next));
+            }
+        }
+
+        strings
+    }
+}
+
+struct Compiler<'a> {
+    rule_set: &'a RuleSet,
+    compiled: HashMap<usize, Dfa>,
+    path: Vec<usize>,
+}
+
+impl<'a> Compiler<'a> {
+    fn compile(&mut self, rule_idx: usize) -> Result<Dfa, Recursive> {
+        if let Some(dfa) = self.compiled.get(&rule_idx) {
+            return Ok(dfa.clone());
+        }
+        if let Some(pos) = self.path.iter().position(|&idx| idx == rule_idx) {
+            let mut cycle = self.path[pos..].to_vec();
+            cycle.push(rule_idx);
+            return Err(Recursive(cycle));
+        }
+
+        self.path.push(rule_idx);
+        let rules = self
+            .rule_set
+            .get(&rule_idx)
+            .map_or(&[][..], |r| r.as_slice());
+        let alternatives = rules
+            .iter()
+            .map(|rule| match rule {
+                Rule::Match(c) => Ok(Dfa::single(*c)),
+                Rule::Pointer(pointers) if pointers.is_empty() => Ok(Dfa::empty_string()),
+                Rule::Pointer(pointers) => {
+                    let parts = pointers
+                        .iter()
+                        .map(|&pointer| self.compile(pointer))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    Ok(Dfa::concat(&parts))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.path.pop();
+
+        let dfa = Dfa::union(&alternatives);
+        self.compiled.insert(rule_idx, dfa.clone());
+
+        Ok(dfa)
+    }
+}
+
+/// Lowers `rule_idx` into a minimal DFA, so matching a message takes a single
+/// pass over it. Only possible when no rule reachable from `rule_idx` refers
+/// back to itself.
+pub fn compile(rule_set: &RuleSet, rule_idx: usize) -> Result<Dfa, Recursive> {
+    let mut compiler = Compiler {
+        rule_set,
+        compiled: HashMap::new(),
+        path: vec![],
+    };
+
+    compiler.compile(rule_idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule_set;
+
+    #[test]
+    fn compiles_finite_rules() {
+        let rule_set =
+            rule_set("0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"");
+        let dfa = compile(&rule_set, 0).unwrap();
+
+        let valid = [
+            "aaaabb", "aaabab", "abbabb", "abbbab", "aabaab", "aabbbb", "abaaab", "ababbb",
+        ];
+        for message in &valid {
+            assert!(dfa.is_match(message), "{}", message);
+        }
+        for message in &["bababa", "aaabbb", "aaaabbb", "", "a"] {
+            assert!(!dfa.is_match(message), "{}", message);
+        }
+        // The minimal automaton accepting exactly these eight strings.
+        assert_eq!(dfa.len(), 10);
+    }
+
+    #[test]
+    fn detects_recursion() {
+        let rule_set = rule_set("0: 1 2\n1: 3 | 3 1\n2: 3\n3: \"a\"");
+
+        assert_eq!(compile(&rule_set, 0).unwrap_err(), Recursive(vec![1, 1]));
+        assert!(compile(&rule_set, 2).is_ok());
+    }
+}
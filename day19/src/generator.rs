@@ -0,0 +1,168 @@
+use crate::automaton::{self, Recursive};
+use crate::{Rule, RuleSet};
+use shared::prelude::*;
+
+/// Every string `rule_idx` matches, in lexicographic order. Only possible
+/// for rules that can't reach themselves.
+pub fn enumerate(rule_set: &RuleSet, rule_idx: usize) -> Result<Vec<String>, Recursive> {
+    automaton::compile(rule_set, rule_idx).map(|dfa| dfa.strings())
+}
+
+/// The number of distinct strings `rule_idx` matches, without listing them.
+pub fn count(rule_set: &RuleSet, rule_idx: usize) -> Result<u128, Recursive> {
+    automaton::compile(rule_set, rule_idx).map(|dfa| dfa.count())
+}
+
+/// Xorshift64*, plenty for picking alternatives.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % n
+    }
+}
+
+/// Draws random strings from any rule set, recursive ones included, by
+/// expanding rules top-down and only picking alternatives that still fit in
+/// the remaining length.
+pub struct Sampler<'a> {
+    rule_set: &'a RuleSet,
+    min_len: HashMap<usize, usize>,
+    rng: Rng,
+    steps: usize,
+}
+
+// Guards against rule sets where an expansion can go on forever without
+// producing characters, like `0: 0 | 1`.
+const MAX_STEPS: usize = 100_000;
+
+impl<'a> Sampler<'a> {
+    pub fn new(rule_set: &'a RuleSet, seed: u64) -> Self {
+        let mut sampler = Sampler {
+            rule_set,
+            min_len: HashMap::new(),
+            rng: Rng(seed.max(1)),
+            steps: 0,
+        };
+
+        loop {
+            let updates: Vec<(usize, usize)> = rule_set
+                .iter()
+                .filter_map(|(&key, rules)| {
+                    let len = rules
+                        .iter()
+                        .filter_map(|rule| sampler.rule_min_len(rule))
+                        .min()?;
+
+                    match sampler.min_len.get(&key) {
+                        Some(&known) if known <= len => None,
+                        _ => Some((key, len)),
+                    }
+                })
+                .collect();
+
+            if updates.is_empty() {
+                break;
+            }
+            sampler.min_len.extend(updates);
+        }
+
+        sampler
+    }
+
+    fn rule_min_len(&self, rule: &Rule) -> Option<usize> {
+        match rule {
+            Rule::Match(_) => Some(1),
+            Rule::Pointer(pointers) => pointers.iter().map(|p| self.min_len.get(p).copied()).sum(),
+        }
+    }
+
+    fn expand(&mut self, rule_idx: usize, budget: usize, out: &mut String) -> Option<()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return None;
+        }
+
+        let rule_set = self.rule_set;
+        let feasible: Vec<&Rule> = rule_set
+            .get(&rule_idx)?
+            .iter()
+            .filter(|rule| self.rule_min_len(rule).map_or(false, |len| len <= budget))
+            .collect();
+        if feasible.is_empty() {
+            return None;
+        }
+
+        match feasible[self.rng.below(feasible.len())] {
+            Rule::Match(c) => out.push(*c),
+            Rule::Pointer(pointers) => {
+                let start = out.chars().count();
+                let mut reserved: usize = pointers.iter().map(|p| self.min_len[p]).sum();
+
+                for pointer in pointers {
+                    reserved -= self.min_len[pointer];
+                    let used = out.chars().count() - start;
+                    self.expand(*pointer, budget - used - reserved, out)?;
+                }
+            }
+        }
+
+        Some(())
+    }
+
+    /// A random string matched by `rule_idx` that is at most `max_len`
+    /// characters long, or `None` if no such string could be found.
+    pub fn sample(&mut self, rule_idx: usize, max_len: usize) -> Option<String> {
+        let mut out = String::new();
+        self.steps = 0;
+
+        self.expand(rule_idx, max_len, &mut out).map(|_| out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::Matcher;
+    use crate::rule_set;
+
+    #[test]
+    fn enumerate_and_count() {
+        let rule_set = rule_set("0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"\n6: 4 | 7 4\n7: 4 | 4 4");
+        let matcher = Matcher::new(&rule_set);
+
+        let strings = enumerate(&rule_set, 0).unwrap();
+        assert_eq!(
+            strings,
+            vec!["aaaabb", "aaabab", "aabaab", "aabbbb", "abaaab", "ababbb", "abbabb", "abbbab"]
+        );
+        assert!(strings.iter().all(|s| matcher.is_valid(s, 0)));
+        assert_eq!(count(&rule_set, 0), Ok(8));
+
+        // Ambiguous: "aa" has two derivations, but is only counted once.
+        assert_eq!(enumerate(&rule_set, 6).unwrap(), vec!["a", "aa", "aaa"]);
+        assert_eq!(count(&rule_set, 6), Ok(3));
+    }
+
+    #[test]
+    fn sample_recursive_rules() {
+        let rule_set =
+            rule_set("0: 1 | 0 2 1\n1: \"a\" | 3 0 4\n2: \"+\"\n3: \"(\"\n4: \")\"\n5: 5 1");
+        let matcher = Matcher::new(&rule_set);
+        let mut sampler = Sampler::new(&rule_set, 19);
+
+        for _ in 0..200 {
+            let sample = sampler.sample(0, 15).unwrap();
+
+            assert!(sample.len() <= 15, "{}", sample);
+            assert!(matcher.is_valid(&sample, 0), "{}", sample);
+        }
+        assert_eq!(sampler.sample(0, 0), None);
+        assert_eq!(sampler.sample(5, 10), None);
+        assert!(count(&rule_set, 0).is_err());
+    }
+}
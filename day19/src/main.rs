@@ -2,14 +2,21 @@
 #[allow(unused_imports)]
 use shared::prelude::*;
 
-const INPUT: &'static str = include_str!("./input.txt");
+mod automaton;
+mod generator;
+mod matcher;
+
+use matcher::Matcher;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const INPUT: &str = include_str!("./input.txt");
 
 type RuleSet = HashMap<usize, Vec<Rule>>;
 type Data<'a> = (RuleSet, Vec<&'a str>);
 type Solution = usize;
 
 #[derive(Debug, Clone)]
-enum Rule {
+pub enum Rule {
     Pointer(Vec<usize>),
     Match(char),
 }
@@ -23,7 +30,7 @@ fn parse_rule(line: &str) -> (usize, Vec<Rule>) {
             if rule.starts_with('"') {
                 Rule::Match(rule.chars().nth(1).unwrap())
             } else {
-                let pointers = rule.split(" ").filter_map(|s| s.parse().ok()).collect();
+                let pointers = rule.split(' ').filter_map(|s| s.parse().ok()).collect();
 
                 Rule::Pointer(pointers)
             }
@@ -33,6 +40,12 @@ fn parse_rule(line: &str) -> (usize, Vec<Rule>) {
     (key, rules)
 }
 
+/// A rule set from rule lines, for the tests of every module.
+#[cfg(test)]
+fn rule_set(rules: &str) -> RuleSet {
+    rules.lines().map(parse_rule).collect()
+}
+
 fn parse_input(input: &str) -> Data {
     let (rules, messages) = input.split("\n\n").collect_tuple().unwrap();
     let rules = rules.lines().map(parse_rule).collect();
@@ -41,32 +54,31 @@ fn parse_input(input: &str) -> Data {
     (rules, messages)
 }
 
-fn is_valid<'a>(message: &'a str, rule_set: &RuleSet, rule_idx: usize) -> Option<&'a str> {
-    let rules = rule_set.get(&rule_idx).unwrap();
-
-    rules.into_iter().find_map(|rule| match rule {
-        Rule::Pointer(pointers) => pointers
-            .into_iter()
-            .fold(Some(message), |message, pointer| match message {
-                Some(s) => is_valid(s, rule_set, *pointer),
-                None => None,
-            }),
-        Rule::Match(c) if message.starts_with(*c) => Some({
-            let (_, tail) = message.split_at(1);
-            tail
-        }),
-        _ => None,
-    })
+// Non-recursive rule sets are compiled to an automaton, anything else falls
+// back to the Earley matcher.
+fn count_valid(rule_set: &RuleSet, messages: &[&str]) -> Solution {
+    match automaton::compile(rule_set, 0) {
+        Ok(dfa) => messages
+            .iter()
+            .filter(|message| dfa.is_match(message))
+            .count(),
+        Err(_) => {
+            let matcher = Matcher::new(rule_set);
+
+            messages
+                .iter()
+                .filter(|message| matcher.is_valid(message, 0))
+                .count()
+        }
+    }
 }
 
 fn solve_a((rule_set, messages): &Data) -> Solution {
-    messages
-        .iter()
-        .filter(|message| is_valid(**message, rule_set, 0) == Some(""))
-        .count()
+    count_valid(rule_set, messages)
 }
 
-fn solve_b((rule_set, messages): &Data) -> Solution {
+// Part B replaces rules 8 and 11 with looping versions.
+fn looped_rules(rule_set: &RuleSet) -> RuleSet {
     let mut rule_set = rule_set.clone();
     rule_set.insert(8, vec![Rule::Pointer(vec![42]), Rule::Pointer(vec![42, 8])]);
     rule_set.insert(
@@ -74,24 +86,141 @@ fn solve_b((rule_set, messages): &Data) -> Solution {
         vec![Rule::Pointer(vec![42, 31]), Rule::Pointer(vec![42, 11, 31])],
     );
 
-    messages
-        .iter()
-        .filter(|message| is_valid(**message, &rule_set, 0) == Some(""))
-        .count()
+    rule_set
+}
+
+fn solve_b((rule_set, messages): &Data) -> Solution {
+    count_valid(&looped_rules(rule_set), messages)
+}
+
+fn explain_rejected(rule_set: &RuleSet, messages: &[&str]) {
+    let matcher = Matcher::new(rule_set);
+
+    for message in messages {
+        if let Some(diagnosis) = matcher.diagnose(message, 0) {
+            println!("{}: {}", message, diagnosis);
+        }
+    }
+}
+
+const USAGE: &str = "\
+usage: day19 [--part a|b] (--why-not | --enumerate RULE | --count RULE |
+                            --sample RULE N MAX_LEN [--seed SEED])
+
+  --part a|b                use the rules of part a (the default) or the
+                            looping rules of part b
+  --why-not                 explain why messages are rejected
+  --enumerate RULE          list every message RULE matches
+  --count RULE              count the messages RULE matches
+  --sample RULE N MAX_LEN   draw N random messages of at most MAX_LEN characters
+  --seed SEED               seed for --sample, taken from the clock by default";
+
+enum Command {
+    WhyNot,
+    Enumerate(usize),
+    Count(usize),
+    Sample {
+        rule: usize,
+        n: usize,
+        max_len: usize,
+    },
+}
+
+fn run_custom(args: &[String], (rule_set, messages): &Data) {
+    let mut looped = false;
+    let mut command = None;
+    let mut seed = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().or_usage(USAGE);
+        match arg.as_str() {
+            "--part" => {
+                looped = match value().as_str() {
+                    "a" => false,
+                    "b" => true,
+                    _ => usage_error(USAGE),
+                }
+            }
+            "--why-not" => command = Some(Command::WhyNot),
+            "--enumerate" => command = Some(Command::Enumerate(value().parse().or_usage(USAGE))),
+            "--count" => command = Some(Command::Count(value().parse().or_usage(USAGE))),
+            "--sample" => {
+                command = Some(Command::Sample {
+                    rule: value().parse().or_usage(USAGE),
+                    n: value().parse().or_usage(USAGE),
+                    max_len: value().parse().or_usage(USAGE),
+                })
+            }
+            "--seed" => seed = Some(value().parse().or_usage(USAGE)),
+            _ => usage_error(USAGE),
+        }
+    }
+
+    let rule_set = if looped {
+        looped_rules(rule_set)
+    } else {
+        rule_set.clone()
+    };
+
+    let command = command.or_usage(USAGE);
+    if let Command::Enumerate(rule) | Command::Count(rule) | Command::Sample { rule, .. } = command
+    {
+        if !rule_set.contains_key(&rule) {
+            eprintln!("no rule {}", rule);
+            std::process::exit(1);
+        }
+    }
+
+    match command {
+        Command::WhyNot => explain_rejected(&rule_set, messages),
+        Command::Enumerate(rule) => match generator::enumerate(&rule_set, rule) {
+            Ok(strings) => strings.iter().for_each(|s| println!("{}", s)),
+            Err(cycle) => println!("rule {} is recursive: {:?}", rule, cycle.0),
+        },
+        Command::Count(rule) => match generator::count(&rule_set, rule) {
+            Ok(count) => println!("{}", count),
+            Err(cycle) => println!("rule {} is recursive: {:?}", rule, cycle.0),
+        },
+        Command::Sample { rule, n, max_len } => {
+            let seed = seed.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_nanos() as u64)
+            });
+            let mut sampler = generator::Sampler::new(&rule_set, seed);
+
+            for idx in 0..n {
+                match sampler.sample(rule, max_len) {
+                    Some(sample) => println!("{}", sample),
+                    None if idx == 0 => println!(
+                        "no sample of at most {} characters found for rule {}",
+                        max_len, rule
+                    ),
+                    None => break,
+                }
+            }
+        }
+    }
 }
 
 fn main() {
     let data = parse_input(INPUT);
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    println!("Part A: {}", solve_a(&data));
-    println!("Part B: {}", solve_b(&data));
+    if args.is_empty() {
+        println!("Part A: {}", solve_a(&data));
+        println!("Part B: {}", solve_b(&data));
+    } else {
+        run_custom(&args, &data);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE: &'static str = "42: 9 14 | 10 1
+    const EXAMPLE: &str = "42: 9 14 | 10 1
 9: 14 27 | 1 26
 10: 23 14 | 28 1
 1: \"a\"
@@ -140,7 +269,11 @@ babaaabbbaaabaababbaabababaaab
 aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba";
 
     #[test]
-    fn examples_a() {}
+    fn examples_a() {
+        let data = parse_input(EXAMPLE);
+
+        assert_eq!(solve_a(&data), 3);
+    }
 
     #[test]
     fn examples_b() {
@@ -0,0 +1,366 @@
+use crate::{Rule, RuleSet};
+use shared::prelude::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: usize,
+    alt: usize,
+    dot: usize,
+    origin: usize,
+}
+
+impl Item {
+    fn advance(self) -> Self {
+        Item {
+            dot: self.dot + 1,
+            ..self
+        }
+    }
+}
+
+enum Next {
+    Rule(usize),
+    Char(char),
+    Done,
+}
+
+/// Why a message was rejected: how many characters could be matched, what
+/// would have been accepted next and the chain of rules expecting it.
+#[derive(Debug, PartialEq)]
+pub struct Diagnosis {
+    pub position: usize,
+    pub found: Option<char>,
+    pub expected: Vec<char>,
+    pub expected_end: bool,
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut expected: Vec<String> = self.expected.iter().map(|c| format!("'{}'", c)).collect();
+        if self.expected_end {
+            expected.push("end of message".to_string());
+        }
+        let found = self
+            .found
+            .map_or("end of message".to_string(), |c| format!("'{}'", c));
+        let path = self.path.iter().map(|idx| idx.to_string()).join(" → ");
+
+        write!(
+            f,
+            "matched {} characters, then expected {} but found {} ({})",
+            self.position,
+            expected.join(" or "),
+            found,
+            path
+        )
+    }
+}
+
+/// Earley recogniser over a `RuleSet`. Every alternative is explored, so any
+/// rule set is decided correctly, left recursive ones included. Rules that
+/// are referenced but never defined match nothing.
+pub struct Matcher<'a> {
+    rule_set: &'a RuleSet,
+    nullable: HashSet<usize>,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(rule_set: &'a RuleSet) -> Self {
+        let mut nullable = HashSet::new();
+
+        loop {
+            let found: Vec<usize> = rule_set
+                .iter()
+                .filter(|(key, _)| !nullable.contains(*key))
+                .filter(|(_, rules)| {
+                    rules.iter().any(|rule| match rule {
+                        Rule::Pointer(pointers) => pointers.iter().all(|p| nullable.contains(p)),
+                        Rule::Match(_) => false,
+                    })
+                })
+                .map(|(&key, _)| key)
+                .collect();
+
+            if found.is_empty() {
+                break;
+            }
+            nullable.extend(found);
+        }
+
+        Matcher { rule_set, nullable }
+    }
+
+    fn alternatives(&self, rule: usize) -> &[Rule] {
+        self.rule_set
+            .get(&rule)
+            .map_or(&[], |rules| rules.as_slice())
+    }
+
+    fn next(&self, item: Item) -> Next {
+        match &self.alternatives(item.rule)[item.alt] {
+            Rule::Pointer(pointers) => pointers
+                .get(item.dot)
+                .map_or(Next::Done, |&p| Next::Rule(p)),
+            Rule::Match(c) if item.dot == 0 => Next::Char(*c),
+            Rule::Match(_) => Next::Done,
+        }
+    }
+
+    /// Runs the recogniser and returns, for every position in `chars`, the
+    /// items that are alive after consuming that many characters.
+    fn chart(&self, chars: &[char], rule_idx: usize) -> Vec<Vec<Item>> {
+        let mut chart: Vec<Vec<Item>> = vec![vec![]; chars.len() + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); chars.len() + 1];
+
+        let mut add = |chart: &mut Vec<Vec<Item>>, pos: usize, item: Item| {
+            if seen[pos].insert(item) {
+                chart[pos].push(item);
+            }
+        };
+
+        for alt in 0..self.alternatives(rule_idx).len() {
+            let item = Item {
+                rule: rule_idx,
+                alt,
+                dot: 0,
+                origin: 0,
+            };
+            add(&mut chart, 0, item);
+        }
+
+        for pos in 0..=chars.len() {
+            let mut idx = 0;
+
+            while idx < chart[pos].len() {
+                let item = chart[pos][idx];
+                idx += 1;
+
+                match self.next(item) {
+                    Next::Done => {
+                        let parents: Vec<Item> = chart[item.origin]
+                            .iter()
+                            .filter(|parent| matches!(self.next(**parent), Next::Rule(r) if r == item.rule))
+                            .copied()
+                            .collect();
+
+                        for parent in parents {
+                            add(&mut chart, pos, parent.advance());
+                        }
+                    }
+                    Next::Rule(rule) => {
+                        for alt in 0..self.alternatives(rule).len() {
+                            let child = Item {
+                                rule,
+                                alt,
+                                dot: 0,
+                                origin: pos,
+                            };
+                            add(&mut chart, pos, child);
+                        }
+                        if self.nullable.contains(&rule) {
+                            add(&mut chart, pos, item.advance());
+                        }
+                    }
+                    Next::Char(c) => {
+                        if chars.get(pos) == Some(&c) {
+                            add(&mut chart, pos + 1, item.advance());
+                        }
+                    }
+                }
+            }
+        }
+
+        chart
+    }
+
+    fn completes(&self, items: &[Item], rule_idx: usize) -> bool {
+        items.iter().any(|item| {
+            item.rule == rule_idx && item.origin == 0 && matches!(self.next(*item), Next::Done)
+        })
+    }
+
+    /// Lengths (in chars) of every prefix of `message` that `rule_idx` matches.
+    fn match_lengths(&self, message: &str, rule_idx: usize) -> Vec<usize> {
+        let chars: Vec<char> = message.chars().collect();
+        let chart = self.chart(&chars, rule_idx);
+
+        (0..=chars.len())
+            .filter(|&pos| self.completes(&chart[pos], rule_idx))
+            .collect()
+    }
+
+    /// Every possible rest of `message` after `rule_idx` consumed a prefix.
+    pub fn remainders<'m>(&self, message: &'m str, rule_idx: usize) -> Vec<&'m str> {
+        let offsets: Vec<usize> = message
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(message.len()))
+            .collect();
+
+        self.match_lengths(message, rule_idx)
+            .into_iter()
+            .map(|length| &message[offsets[length]..])
+            .collect()
+    }
+
+    pub fn is_valid(&self, message: &str, rule_idx: usize) -> bool {
+        self.remainders(message, rule_idx).contains(&"")
+    }
+
+    /// Explains why `rule_idx` doesn't match all of `message`, or returns
+    /// `None` if it does.
+    pub fn diagnose(&self, message: &str, rule_idx: usize) -> Option<Diagnosis> {
+        let chars: Vec<char> = message.chars().collect();
+        let chart = self.chart(&chars, rule_idx);
+
+        if self.completes(&chart[chars.len()], rule_idx) {
+            return None;
+        }
+
+        let position = (0..=chars.len())
+            .rev()
+            .find(|&pos| !chart[pos].is_empty())
+            .unwrap_or(0);
+        let expecting: Vec<(char, Item)> = chart[position]
+            .iter()
+            .filter_map(|&item| match self.next(item) {
+                Next::Char(c) => Some((c, item)),
+                _ => None,
+            })
+            .collect();
+        let path = match expecting.first() {
+            Some((_, item)) => self.rule_path(&chart, rule_idx, (item.rule, item.origin)),
+            None => vec![rule_idx],
+        };
+
+        Some(Diagnosis {
+            position,
+            found: chars.get(position).copied(),
+            expected: expecting.iter().map(|&(c, _)| c).sorted().dedup().collect(),
+            expected_end: position < chars.len() && self.completes(&chart[position], rule_idx),
+            path,
+        })
+    }
+
+    // Finds the shortest chain of predictions from `rule_idx` at the start of
+    // the message to `target`, a `(rule, origin)` pair present in the chart.
+    fn rule_path(
+        &self,
+        chart: &[Vec<Item>],
+        rule_idx: usize,
+        target: (usize, usize),
+    ) -> Vec<usize> {
+        let root = (rule_idx, 0);
+        let mut children: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(target);
+
+        while let Some(node) = queue.pop_front() {
+            if node == root {
+                break;
+            }
+
+            let (rule, origin) = node;
+            for parent in &chart[origin] {
+                let parent_node = (parent.rule, parent.origin);
+                let predicts = matches!(self.next(*parent), Next::Rule(r) if r == rule);
+
+                if predicts && parent_node != target && !children.contains_key(&parent_node) {
+                    children.insert(parent_node, node);
+                    queue.push_back(parent_node);
+                }
+            }
+        }
+
+        let mut path = vec![rule_idx];
+        let mut node = root;
+        while node != target {
+            node = children[&node];
+            path.push(node.0);
+        }
+
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule_set;
+
+    #[test]
+    fn backtracking() {
+        let rule_set = rule_set("0: 1 2\n1: 3 | 3 3\n2: 3 4\n3: \"a\"\n4: \"b\"");
+        let matcher = Matcher::new(&rule_set);
+
+        assert!(matcher.is_valid("aab", 0));
+        assert!(matcher.is_valid("aaab", 0));
+        assert!(!matcher.is_valid("aaaab", 0));
+        assert_eq!(matcher.remainders("aaab", 1), vec!["aab", "ab"]);
+    }
+
+    #[test]
+    fn left_recursion() {
+        let rule_set = rule_set("0: 0 1 | 1\n1: \"a\" | 2 1 3\n2: \"(\"\n3: \")\"");
+        let matcher = Matcher::new(&rule_set);
+
+        assert!(matcher.is_valid("a", 0));
+        assert!(matcher.is_valid("aa(a)a", 0));
+        assert!(matcher.is_valid("((a))", 0));
+        assert!(!matcher.is_valid("(a", 0));
+        assert_eq!(matcher.remainders("aab", 0), vec!["ab", "b"]);
+    }
+
+    #[test]
+    fn nullable_rules() {
+        let rule_set = rule_set("0: 1 2 1\n1: 2 | 3\n2: \n3: \"a\"");
+        let matcher = Matcher::new(&rule_set);
+
+        assert!(matcher.is_valid("", 0));
+        assert!(matcher.is_valid("a", 0));
+        assert!(matcher.is_valid("aa", 0));
+        assert!(!matcher.is_valid("aaa", 0));
+    }
+
+    #[test]
+    fn diagnose() {
+        let rule_set = rule_set("0: 1 2\n1: 3 | 3 3\n2: 3 4\n3: \"a\"\n4: \"b\"");
+        let matcher = Matcher::new(&rule_set);
+
+        assert_eq!(matcher.diagnose("aab", 0), None);
+
+        let diagnosis = matcher.diagnose("aaac", 0).unwrap();
+        assert_eq!(
+            diagnosis,
+            Diagnosis {
+                position: 3,
+                found: Some('c'),
+                expected: vec!['b'],
+                expected_end: false,
+                path: vec![0, 2, 4],
+            }
+        );
+        assert_eq!(
+            diagnosis.to_string(),
+            "matched 3 characters, then expected 'b' but found 'c' (0 → 2 → 4)"
+        );
+
+        let diagnosis = matcher.diagnose("a", 0).unwrap();
+        assert_eq!(diagnosis.position, 1);
+        assert_eq!(diagnosis.found, None);
+        assert_eq!(diagnosis.expected, vec!['a']);
+        assert_eq!(diagnosis.path, vec![0, 1, 3]);
+
+        let diagnosis = matcher.diagnose("aabb", 0).unwrap();
+        assert_eq!(diagnosis.position, 3);
+        assert!(diagnosis.expected.is_empty());
+        assert!(diagnosis.expected_end);
+        assert_eq!(
+            diagnosis.to_string(),
+            "matched 3 characters, then expected end of message but found 'b' (0)"
+        );
+    }
+}
@@ -1,72 +1,382 @@
-#![feature(str_split_once)]
 #[allow(unused_imports)]
 use shared::prelude::*;
-use std::collections::VecDeque;
+use std::fmt;
 
-const INPUT: &'static str = "614752839";
+const INPUT: &str = include_str!("./input.txt");
 
-type Data = VecDeque<u64>;
+type Data = Vec<usize>;
 
-fn parse_input(input: &str) -> Data {
+#[derive(Debug, PartialEq)]
+struct ParseError(char);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' is not a cup label", self.0)
+    }
+}
+
+fn parse_input(input: &str) -> Result<Data, ParseError> {
     input
+        .trim()
         .chars()
-        .map(|c| c.to_digit(10).unwrap_or(0) as u64)
+        .map(|c| c.to_digit(10).map(|d| d as usize).ok_or(ParseError(c)))
         .collect()
 }
 
-fn step(data: &mut Data) -> Option<()> {
-    let l = data.len() as u64;
+/// Why a game can't be played.
+#[derive(Debug, PartialEq)]
+enum GameError {
+    Labels,
+    Cups(usize),
+    PickUp(usize),
+}
 
-    let p = data.pop_front()?;
-    let grab = [data.pop_front()?, data.pop_front()?, data.pop_front()?];
-    let mut c: u64 = p - 1;
-    while grab.contains(&c) || c == 0 {
-        if c == 0 {
-            c += l + 1;
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Labels => write!(f, "the labels must be 1 to n, each once"),
+            GameError::Cups(0) => write!(f, "need at least one cup"),
+            GameError::Cups(labels) => write!(f, "need at least {} cups", labels),
+            GameError::PickUp(cups) => write!(f, "can't pick up all of {} cups", cups),
         }
-        c = c - 1;
     }
-    let idx = data.iter().position(|&n| n == c)?;
-    data.rotate_left(idx + 1);
-    data.push_front(grab[2]);
-    data.push_front(grab[1]);
-    data.push_front(grab[0]);
-    data.rotate_right(idx + 1);
-    data.push_back(p);
+}
 
-    Some(())
+/// How a game is played: the starting labels, filled up with the following
+/// labels in order until there are `cups` cups, and how many cups are picked
+/// up in each of the `moves` moves.
+#[derive(Debug, Clone)]
+struct Game {
+    labels: Vec<usize>,
+    cups: usize,
+    moves: usize,
+    pick_up: usize,
 }
 
-fn to_int(data: &Data) -> u64 {
-    let mut data = data.clone();
-    let idx = data.iter().position(|&n| n == 1).unwrap();
-    data.rotate_left(idx);
+impl Game {
+    /// Checks what `CupCircle::new` asserts.
+    fn validate(&self) -> Result<(), GameError> {
+        let mut labels = self.labels.clone();
+        labels.sort_unstable();
+
+        if !labels.iter().copied().eq(1..=labels.len()) {
+            Err(GameError::Labels)
+        } else if self.cups == 0 || self.cups < labels.len() {
+            Err(GameError::Cups(labels.len()))
+        } else if self.pick_up >= self.cups {
+            Err(GameError::PickUp(self.cups))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn part_a(labels: &[usize]) -> Self {
+        Game {
+            labels: labels.to_vec(),
+            cups: labels.len(),
+            moves: 100,
+            pick_up: 3,
+        }
+    }
+
+    fn part_b(labels: &[usize]) -> Self {
+        Game {
+            labels: labels.to_vec(),
+            cups: 1_000_000,
+            moves: 10_000_000,
+            pick_up: 3,
+        }
+    }
+
+    fn circle(&self) -> CupCircle {
+        let labels: Vec<usize> = self
+            .labels
+            .iter()
+            .copied()
+            .chain(self.labels.len() + 1..=self.cups)
+            .collect();
+
+        CupCircle::new(&labels, self.pick_up)
+    }
+
+    fn play(&self) -> CupCircle {
+        let mut circle = self.circle();
+        circle.play(self.moves);
+
+        circle
+    }
+}
+
+/// The cups as a successor array: `next[label]` is the label of the cup
+/// clockwise of `label`, so a move only has to rewire three links. Labels
+/// are expected to be `1..=n`, index 0 is unused. Stored as `u32` to keep
+/// the million-cup array small enough to stay mostly in cache.
+struct CupCircle {
+    next: Vec<u32>,
+    current: usize,
+    pick_up: usize,
+    picked: Vec<usize>,
+    moves: usize,
+}
+
+impl CupCircle {
+    fn new(labels: &[usize], pick_up: usize) -> Self {
+        let mut next = vec![0; labels.len() + 1];
+
+        for (&label, &successor) in labels.iter().zip(labels.iter().cycle().skip(1)) {
+            assert!(
+                (1..=labels.len()).contains(&label) && next[label] == 0,
+                "labels must be a permutation of 1..={}",
+                labels.len()
+            );
+            next[label] = successor as u32;
+        }
+        assert!(
+            pick_up < labels.len(),
+            "can't pick up {} of {} cups",
+            pick_up,
+            labels.len()
+        );
+
+        CupCircle {
+            next,
+            current: labels[0],
+            pick_up,
+            picked: Vec::with_capacity(pick_up),
+            moves: 0,
+        }
+    }
+
+    fn step(&mut self) {
+        let max_label = self.next.len() - 1;
+
+        self.picked.clear();
+        let mut cup = self.current;
+        for _ in 0..self.pick_up {
+            cup = self.next[cup] as usize;
+            self.picked.push(cup);
+        }
+
+        let mut destination = self.current;
+        loop {
+            destination = if destination == 1 {
+                max_label
+            } else {
+                destination - 1
+            };
+            if !self.picked.contains(&destination) {
+                break;
+            }
+        }
+
+        if let (Some(&first), Some(&last)) = (self.picked.first(), self.picked.last()) {
+            self.next[self.current] = self.next[last];
+            self.next[last] = self.next[destination];
+            self.next[destination] = first as u32;
+        }
+        self.current = self.next[self.current] as usize;
+        self.moves += 1;
+    }
+
+    fn play(&mut self, moves: usize) {
+        for _ in 0..moves {
+            self.step();
+        }
+    }
+
+    /// The `n` labels clockwise of `label`.
+    fn after(&self, label: usize, n: usize) -> Vec<usize> {
+        let mut labels = Vec::with_capacity(n);
+        let mut cup = label;
+
+        for _ in 0..n {
+            cup = self.next[cup] as usize;
+            labels.push(cup);
+        }
+
+        labels
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut cups = vec![self.current];
+        cups.extend(self.after(self.current, self.next.len() - 2));
 
-    data.iter().skip(1).fold(0u64, |acc, &n| acc * 10 + n)
+        Snapshot {
+            cups,
+            moves: self.moves,
+        }
+    }
 }
 
-fn solve_a(mut data: Data) -> u64 {
-    for _ in 0..100 {
-        step(&mut data);
+/// The circle after `moves` moves, listed clockwise from the current cup.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    cups: Vec<usize>,
+    moves: usize,
+}
+
+/// Prints the cups like the puzzle does, where the current cup shifts one
+/// place to the right with every move: `3 (2) 8 9 1 5 4 6 7`.
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shift = self.moves % self.cups.len();
+        let cups = self.cups[self.cups.len() - shift..]
+            .iter()
+            .chain(&self.cups[..self.cups.len() - shift])
+            .map(|&cup| {
+                if cup == self.cups[0] {
+                    format!("({})", cup)
+                } else {
+                    cup.to_string()
+                }
+            })
+            .join(" ");
+
+        write!(f, "{}", cups)
     }
+}
+
+fn solve_a(data: &[usize]) -> u64 {
+    Game::part_a(data)
+        .play()
+        .after(1, data.len() - 1)
+        .into_iter()
+        .fold(0u64, |acc, n| acc * 10 + n as u64)
+}
 
-    to_int(&data)
+fn solve_b(data: &[usize]) -> u64 {
+    Game::part_b(data)
+        .play()
+        .after(1, 2)
+        .into_iter()
+        .map(|n| n as u64)
+        .product()
 }
 
-fn solve_b(mut data: Data) -> u64 {
-    data.extend(10..=1000000);
-    for _ in 0..10_000_000 {
-        step(&mut data);
+const USAGE: &str = "\
+usage: day23 [--labels LABELS] [--cups N] [--moves N] [--pick-up N] [--show]
+
+Plays a custom game and prints the labels clockwise of cup 1. With --show
+the circle is printed before every move, like in the puzzle.";
+
+fn play_custom(args: &[String], data: &[usize]) {
+    let mut game = Game::part_a(data);
+    let mut cups = None;
+    let mut show = false;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().or_usage(USAGE);
+        match arg.as_str() {
+            "--labels" => game.labels = parse_input(value()).or_usage(USAGE),
+            "--cups" => cups = Some(value().parse().or_usage(USAGE)),
+            "--moves" => game.moves = value().parse().or_usage(USAGE),
+            "--pick-up" => game.pick_up = value().parse().or_usage(USAGE),
+            "--show" => show = true,
+            _ => usage_error(USAGE),
+        }
     }
+    // Without --cups, other labels still fill up the puzzle's circle.
+    game.cups = cups.unwrap_or_else(|| game.cups.max(game.labels.len()));
+    game.validate().or_usage(USAGE);
 
-    let idx = data.iter().position(|&n| n == 1).unwrap();
-    data.rotate_left(idx + 1);
+    let mut circle = game.circle();
+    for n in 1..=game.moves {
+        if show {
+            println!("-- move {} --\ncups: {}", n, circle.snapshot());
+        }
+        circle.step();
+    }
+    if show {
+        println!("-- final --\ncups: {}", circle.snapshot());
+    }
 
-    data.pop_front().unwrap() * data.pop_front().unwrap()
+    println!("{}", circle.after(1, game.cups - 1).iter().join(" "));
 }
 
 fn main() {
-    let data = parse_input(INPUT);
-    println!("{:?}", solve_a(data.clone()));
-    println!("{:?}", solve_b(data.clone()));
+    let data = parse_input(INPUT).unwrap_or_else(|error| {
+        eprintln!("invalid input: {}", error);
+        std::process::exit(1);
+    });
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() {
+        println!("{:?}", solve_a(&data));
+        println!("{:?}", solve_b(&data));
+    } else {
+        play_custom(&args, &data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "389125467";
+
+    #[test]
+    fn examples_a() {
+        let data = parse_input(EXAMPLE).unwrap();
+        let game = Game {
+            moves: 10,
+            ..Game::part_a(&data)
+        };
+
+        assert_eq!(game.play().after(1, 8), vec![9, 2, 6, 5, 8, 3, 7, 4]);
+        assert_eq!(solve_a(&data), 67384529);
+    }
+
+    #[test]
+    fn examples_b() {
+        let data = parse_input(EXAMPLE).unwrap();
+
+        assert_eq!(solve_b(&data), 149245887792);
+    }
+
+    #[test]
+    fn snapshots() {
+        let data = parse_input(EXAMPLE).unwrap();
+        let mut circle = Game::part_a(&data).circle();
+
+        assert_eq!(circle.snapshot().to_string(), "(3) 8 9 1 2 5 4 6 7");
+        circle.step();
+        assert_eq!(circle.snapshot().to_string(), "3 (2) 8 9 1 5 4 6 7");
+        circle.play(8);
+        assert_eq!(circle.snapshot().to_string(), "(5) 7 4 1 8 3 9 2 6");
+        circle.step();
+        assert_eq!(circle.snapshot().to_string(), "5 (8) 3 7 4 1 9 2 6");
+    }
+
+    #[test]
+    fn custom_parameters() {
+        let game = Game {
+            labels: vec![2, 1],
+            cups: 5,
+            moves: 1,
+            pick_up: 2,
+        };
+        let circle = game.play();
+
+        // 2 picks up 1 and 3; the destination skips 1 and wraps around to 5.
+        assert_eq!(circle.snapshot().cups, vec![4, 5, 1, 3, 2]);
+    }
+
+    #[test]
+    fn invalid_games() {
+        assert_eq!(parse_input("3a2"), Err(ParseError('a')));
+
+        let game = |labels: &[usize], cups, pick_up| Game {
+            labels: labels.to_vec(),
+            cups,
+            moves: 1,
+            pick_up,
+        };
+        assert_eq!(game(&[2, 1], 5, 3).validate(), Ok(()));
+        assert_eq!(game(&[5], 5, 3).validate(), Err(GameError::Labels));
+        assert_eq!(game(&[1, 1], 5, 3).validate(), Err(GameError::Labels));
+        assert_eq!(game(&[], 0, 0).validate(), Err(GameError::Cups(0)));
+        assert_eq!(game(&[2, 1], 1, 0).validate(), Err(GameError::Cups(2)));
+        assert_eq!(game(&[2, 1], 5, 5).validate(), Err(GameError::PickUp(5)));
+    }
 }
@@ -3,3 +3,4 @@ pub mod debug;
 pub mod foldresult;
 pub mod iterators;
 pub mod prelude;
+pub mod usage;
@@ -1,5 +1,6 @@
 pub use crate::debug::ShowDebug;
 pub use crate::foldresult::*;
 pub use crate::iterators::BlockSplit;
+pub use crate::usage::{usage_error, OrUsage};
 pub use itertools::Itertools;
 pub use std::collections::{HashMap, HashSet};
@@ -0,0 +1,29 @@
+use std::fmt::Display;
+
+/// Prints `usage` to stderr and exits with status 1, for command lines that
+/// can't be understood.
+pub fn usage_error(usage: &str) -> ! {
+    eprintln!("{}", usage);
+    std::process::exit(1);
+}
+
+/// Unwraps a parsed command line argument, or reports the usage and exits
+/// like `usage_error` when it is missing or invalid.
+pub trait OrUsage<T> {
+    fn or_usage(self, usage: &str) -> T;
+}
+
+impl<T> OrUsage<T> for Option<T> {
+    fn or_usage(self, usage: &str) -> T {
+        self.unwrap_or_else(|| usage_error(usage))
+    }
+}
+
+impl<T, E: Display> OrUsage<T> for Result<T, E> {
+    fn or_usage(self, usage: &str) -> T {
+        self.unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            usage_error(usage)
+        })
+    }
+}